@@ -1,4 +1,8 @@
-use suffix_array::{SaIsBuilder, NaiveBuilder, SuffixArrayBuilder, validate_suffix_array};
+use suffix_array::{BlockCompressor, SaIsBuilder, NaiveBuilder, SuffixArrayBuilder, validate_suffix_array};
+use suffix_array::{minimal_absent_words, shortest_unique_substrings};
+use suffix_array::{mlr_find_range, LcpLrIndex};
+use suffix_array::{keyword_in_context, HitOrder};
+use suffix_array::{read_sequences, SequenceIndex};
 
 // Whether to print verbose information for debugging.
 #[allow(dead_code)]
@@ -33,19 +37,225 @@ fn compute_suffix_array(sa_builders: &[Box<dyn SuffixArrayBuilder>], filename: &
         }
 
         if VALIDATION_ENABLED {
-            validate_suffix_array(&text_bytes, suffix_array.array());
+            validate_suffix_array(text_bytes, suffix_array.array());
         }
     }
     println!("Done");
 }
 
+// Compresses a file, and reports the throughput.
+fn compress_file(input: &str, output: &str) -> std::io::Result<()> {
+    let data = std::fs::read(input)?;
+    let compressor = BlockCompressor::new(Box::new(SaIsBuilder::new()));
+
+    let start = std::time::Instant::now();
+    let compressed = compressor.compress(&data);
+    let elapsed = start.elapsed().as_secs_f64();
+
+    std::fs::write(output, &compressed)?;
+    println!(
+        "{} -> {} bytes in {:.3}s ({:.2} MB/s)",
+        data.len(),
+        compressed.len(),
+        elapsed,
+        data.len() as f64 / elapsed / 1e6
+    );
+    Ok(())
+}
+
+fn decompress_file(input: &str, output: &str) -> std::io::Result<()> {
+    let data = std::fs::read(input)?;
+    let decompressed = suffix_array::compress::decompress(&data)?;
+    std::fs::write(output, decompressed)
+}
+
+// Prints the shortest unique substring covering each position, and the minimal absent words.
+fn dump_substrings(filename: &str, max_absent_len: usize) -> std::io::Result<()> {
+    let text = std::fs::read(filename)?;
+    if text.is_empty() {
+        return Ok(());
+    }
+    let suffix_array = SaIsBuilder::new().build(&text);
+    let escape = |bytes: &[u8]| String::from_utf8_lossy(bytes).escape_debug().to_string();
+
+    println!("# Shortest unique substrings: pos, start, length, substring");
+    let unique_substrings = shortest_unique_substrings(&text, suffix_array.array());
+    for (pos, range) in unique_substrings.iter().enumerate() {
+        let substring = escape(&text[range.clone()]);
+        println!("{}\t{}\t{}\t{}", pos, range.start, range.len(), substring);
+    }
+
+    println!("# Minimal absent words of length <= {}", max_absent_len);
+    for word in minimal_absent_words(&text, suffix_array.array(), max_absent_len) {
+        println!("{}", escape(&word));
+    }
+    Ok(())
+}
+
+// Prints each hit of the pattern with its line, column and context, like grep.
+fn search_file(
+    filename: &str,
+    pattern: &str,
+    context: usize,
+    order: HitOrder,
+) -> std::io::Result<()> {
+    let text = std::fs::read(filename)?;
+    if text.is_empty() {
+        return Ok(());
+    }
+    let suffix_array = SaIsBuilder::new().build(&text);
+    let escape = |bytes: &[u8]| String::from_utf8_lossy(bytes).escape_debug().to_string();
+
+    let sa = suffix_array.array();
+    for hit in keyword_in_context(&text, sa, pattern.as_bytes(), context, context, order) {
+        println!(
+            "{}:{}:{:>width$}[{}]{}",
+            hit.line,
+            hit.column,
+            escape(hit.left),
+            escape(hit.keyword),
+            escape(hit.right),
+            width = context
+        );
+    }
+    Ok(())
+}
+
+// Times the plain, mlr and LCP-LR searches for patterns of the given length taken from the text.
+fn bench_search(filename: &str, pattern_len: usize) -> std::io::Result<()> {
+    let text = std::fs::read(filename)?;
+    if text.is_empty() || text.len() < pattern_len {
+        return Ok(());
+    }
+    let suffix_array = SaIsBuilder::new().build(&text);
+    let sa = suffix_array.array();
+    let index = LcpLrIndex::new(&text, sa);
+
+    let count = 10000;
+    let step = ((text.len() - pattern_len) / count).max(1);
+    let patterns: Vec<&[u8]> = (0..=text.len() - pattern_len)
+        .step_by(step)
+        .map(|pos| &text[pos..pos + pattern_len])
+        .collect();
+
+    let time = |name: &str, find_range: &dyn Fn(&[u8]) -> std::ops::Range<usize>| {
+        let start = std::time::Instant::now();
+        let occurrences: usize = patterns.iter().map(|pattern| find_range(pattern).len()).sum();
+        let elapsed = start.elapsed().as_secs_f64();
+        println!(
+            "{:>6}: {} patterns, {} occurrences in {:.3}s ({:.2} us/pattern)",
+            name,
+            patterns.len(),
+            occurrences,
+            elapsed,
+            elapsed * 1e6 / patterns.len() as f64
+        );
+    };
+    time("plain", &|pattern| suffix_array.find_range(&text, pattern));
+    time("mlr", &|pattern| mlr_find_range(&text, sa, pattern));
+    time("lcp-lr", &|pattern| index.find_range(pattern));
+    Ok(())
+}
+
+// Indexes the records of a FASTA or FASTQ file, and prints where each pattern occurs, on the
+// forward strand or on both strands.
+fn build_fasta(filename: &str, patterns: &[String], both_strands: bool) -> std::io::Result<()> {
+    let data = std::fs::read(filename)?;
+    let records = read_sequences(&data)?;
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let start = std::time::Instant::now();
+    let index = SequenceIndex::new(records);
+    println!(
+        "Indexed {} records, {} bases in {:.3}s",
+        index.len(),
+        index.total_len(),
+        start.elapsed().as_secs_f64()
+    );
+
+    for pattern in patterns {
+        if both_strands {
+            for (name, offset, strand) in index.locate_both_strands(pattern.as_bytes()) {
+                println!("{}\t{}\t{}\t{}", pattern, name, offset, strand);
+            }
+        } else {
+            for (name, offset) in index.locate(pattern.as_bytes()) {
+                println!("{}\t{}\t{}", pattern, name, offset);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn usage(program: &str) -> ! {
+    eprintln!("Usage:");
+    eprintln!("  {} compress|decompress <input> <output>", program);
+    eprintln!("  {} substrings <file> [max_absent_len]", program);
+    eprintln!("  {} bench-search <file> [pattern_len]", program);
+    eprintln!("  {} search <file> <pattern> [--context <bytes>] [--sort text|suffix]", program);
+    eprintln!("  {} build --fasta <file> [--both-strands] [pattern...]", program);
+    std::process::exit(2);
+}
+
 fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        Some("compress") if args.len() == 4 => return compress_file(&args[2], &args[3]),
+        Some("decompress") if args.len() == 4 => return decompress_file(&args[2], &args[3]),
+        Some("substrings") if args.len() == 3 || args.len() == 4 => {
+            let max_absent_len = match args.get(3) {
+                Some(arg) => arg.parse().unwrap_or_else(|_| usage(&args[0])),
+                None => 4,
+            };
+            return dump_substrings(&args[2], max_absent_len);
+        }
+        Some("bench-search") if args.len() == 3 || args.len() == 4 => {
+            let pattern_len = match args.get(3) {
+                Some(arg) => arg.parse().unwrap_or_else(|_| usage(&args[0])),
+                None => 100,
+            };
+            return bench_search(&args[2], pattern_len);
+        }
+        Some("search") if args.len() >= 4 => {
+            let (mut context, mut order) = (30, HitOrder::Text);
+            let mut options = args[4..].iter();
+            while let Some(option) = options.next() {
+                let value = options.next().unwrap_or_else(|| usage(&args[0]));
+                match (option.as_str(), value.as_str()) {
+                    ("--context", _) => {
+                        context = value.parse().unwrap_or_else(|_| usage(&args[0]))
+                    }
+                    ("--sort", "text") => order = HitOrder::Text,
+                    ("--sort", "suffix") => order = HitOrder::Suffix,
+                    _ => usage(&args[0]),
+                }
+            }
+            return search_file(&args[2], &args[3], context, order);
+        }
+        Some("build") if args.len() >= 4 && args[2] == "--fasta" => {
+            let mut both_strands = false;
+            let mut patterns: Vec<String> = Vec::new();
+            for arg in &args[4..] {
+                match arg.as_str() {
+                    "--both-strands" => both_strands = true,
+                    _ if arg.starts_with("--") => usage(&args[0]),
+                    _ => patterns.push(arg.clone()),
+                }
+            }
+            return build_fasta(&args[3], &patterns, both_strands);
+        }
+        Some(_) => usage(&args[0]),
+        None => {}
+    }
+
     let sa_is_builder = Box::new(SaIsBuilder::new());
-    let naive_builder = Box::new(NaiveBuilder::new());
+    let _naive_builder = Box::new(NaiveBuilder::new());
 
-    //let builders : [Box<dyn SuffixArrayBuilder>; 2] = [sa_is_builder, naive_builder];
+    //let builders : [Box<dyn SuffixArrayBuilder>; 2] = [sa_is_builder, _naive_builder];
     let builders : [Box<dyn SuffixArrayBuilder>; 1] = [sa_is_builder];
-    
+
     compute_suffix_array(&builders, "/tmp/test.txt");
     Ok(())
 }
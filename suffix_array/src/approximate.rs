@@ -0,0 +1,249 @@
+// Approximate pattern search over a suffix array, with up to k mismatches (Hamming distance) or
+// up to k edits (edit distance).
+//
+// Both searches walk down the suffix array ranges as in a suffix tree, one char at a time, and
+// branch on every char that follows the current prefix. A branch is pruned as soon as its
+// prefix can no longer be within distance k of the pattern.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::search::{child_ranges, narrow_range};
+use super::suffix_array::TextSize;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ApproximateMatch {
+    // Start of the match in the text.
+    pub pos: TextSize,
+
+    // Length of the matched text.
+    pub length: TextSize,
+
+    // Number of mismatches or edits between the pattern and the matched text.
+    pub distance: TextSize,
+}
+
+// Finds all text positions where the pattern occurs with at most k mismatches.
+// Returns the matches in text order.
+pub fn hamming_search(
+    text: &[u8],
+    sa: &[TextSize],
+    pattern: &[u8],
+    k: TextSize,
+) -> Vec<ApproximateMatch> {
+    let mut matches: Vec<ApproximateMatch> = Vec::new();
+    hamming_search_range(text, sa, pattern, k, 0..sa.len(), 0, 0, &mut matches);
+    matches.sort_unstable_by_key(|m| m.pos);
+    matches
+}
+
+// Searches the suffixes in range, which match pattern[..depth] with the given mismatches.
+#[allow(clippy::too_many_arguments)]
+fn hamming_search_range(
+    text: &[u8],
+    sa: &[TextSize],
+    pattern: &[u8],
+    k: TextSize,
+    mut range: Range<usize>,
+    mut depth: usize,
+    mismatches: TextSize,
+    matches: &mut Vec<ApproximateMatch>,
+) {
+    // With no mismatches left, the rest of the pattern must match exactly.
+    if mismatches == k {
+        while depth < pattern.len() && !range.is_empty() {
+            range = narrow_range(text, sa, range, depth, pattern[depth]);
+            depth += 1;
+        }
+    }
+
+    if depth == pattern.len() {
+        for &pos in sa[range].iter() {
+            matches.push(ApproximateMatch {
+                pos,
+                length: pattern.len() as TextSize,
+                distance: mismatches,
+            });
+        }
+        return;
+    }
+
+    for (ch, child) in child_ranges(text, sa, range, depth) {
+        let cost = if ch == pattern[depth] { 0 } else { 1 };
+        hamming_search_range(
+            text,
+            sa,
+            pattern,
+            k,
+            child,
+            depth + 1,
+            mismatches + cost,
+            matches,
+        );
+    }
+}
+
+// Finds all text positions where a prefix of the suffix is within edit distance k of the
+// pattern. For each position, the match with the smallest distance is returned, and the
+// shortest one among those. Returns the matches in text order.
+pub fn edit_distance_search(
+    text: &[u8],
+    sa: &[TextSize],
+    pattern: &[u8],
+    k: TextSize,
+) -> Vec<ApproximateMatch> {
+    // distances[j] = edit distance between pattern[..j] and the current prefix.
+    let distances: Vec<TextSize> = (0..=pattern.len() as TextSize).collect();
+    let mut best: HashMap<TextSize, ApproximateMatch> = HashMap::new();
+    edit_distance_search_range(text, sa, pattern, k, 0..sa.len(), 0, &distances, &mut best);
+
+    let mut matches: Vec<ApproximateMatch> = best.into_values().collect();
+    matches.sort_unstable_by_key(|m| m.pos);
+    matches
+}
+
+// Searches the suffixes in range, which share a prefix of length depth. distances is the
+// dynamic programming column for that prefix.
+#[allow(clippy::too_many_arguments)]
+fn edit_distance_search_range(
+    text: &[u8],
+    sa: &[TextSize],
+    pattern: &[u8],
+    k: TextSize,
+    range: Range<usize>,
+    depth: usize,
+    distances: &[TextSize],
+    best: &mut HashMap<TextSize, ApproximateMatch>,
+) {
+    let distance = distances[pattern.len()];
+    if distance <= k {
+        for &pos in sa[range.clone()].iter() {
+            let candidate = ApproximateMatch {
+                pos,
+                length: depth as TextSize,
+                distance,
+            };
+            best.entry(pos)
+                .and_modify(|m| {
+                    if (distance, candidate.length) < (m.distance, m.length) {
+                        *m = candidate;
+                    }
+                })
+                .or_insert(candidate);
+        }
+    }
+
+    for (ch, child) in child_ranges(text, sa, range, depth) {
+        // Compute the column for the prefix extended with ch.
+        let mut next: Vec<TextSize> = Vec::with_capacity(distances.len());
+        next.push(distances[0] + 1);
+        for j in 1..distances.len() {
+            let substitution = distances[j - 1] + if pattern[j - 1] == ch { 0 } else { 1 };
+            let insertion = distances[j] + 1;
+            let deletion = next[j - 1] + 1;
+            next.push(substitution.min(insertion).min(deletion));
+        }
+
+        // Extending the prefix further cannot bring the distance below the column minimum.
+        if next.iter().min().unwrap() <= &k {
+            edit_distance_search_range(text, sa, pattern, k, child, depth + 1, &next, best);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    fn edit_distance(a: &[u8], b: &[u8]) -> TextSize {
+        let mut column: Vec<TextSize> = (0..=a.len() as TextSize).collect();
+        for (i, &ch) in b.iter().enumerate() {
+            let mut next: Vec<TextSize> = vec![i as TextSize + 1];
+            for j in 1..=a.len() {
+                let substitution = column[j - 1] + if a[j - 1] == ch { 0 } else { 1 };
+                next.push(substitution.min(column[j] + 1).min(next[j - 1] + 1));
+            }
+            column = next;
+        }
+        column[a.len()]
+    }
+
+    fn naive_hamming(text: &[u8], pattern: &[u8], k: TextSize) -> Vec<ApproximateMatch> {
+        (0..=text.len().saturating_sub(pattern.len()))
+            .filter(|&pos| pos + pattern.len() <= text.len())
+            .map(|pos| ApproximateMatch {
+                pos: pos as TextSize,
+                length: pattern.len() as TextSize,
+                distance: pattern
+                    .iter()
+                    .zip(text[pos..].iter())
+                    .filter(|(a, b)| a != b)
+                    .count() as TextSize,
+            })
+            .filter(|m| m.distance <= k)
+            .collect()
+    }
+
+    fn naive_edit(text: &[u8], pattern: &[u8], k: TextSize) -> Vec<ApproximateMatch> {
+        (0..text.len())
+            .filter_map(|pos| {
+                (0..=text.len() - pos)
+                    .map(|length| ApproximateMatch {
+                        pos: pos as TextSize,
+                        length: length as TextSize,
+                        distance: edit_distance(pattern, &text[pos..pos + length]),
+                    })
+                    .min_by_key(|m| (m.distance, m.length))
+                    .filter(|m| m.distance <= k)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn banana() {
+        let text = b"banana";
+        let sa = SaIsBuilder::new().build(text);
+        let positions = |matches: Vec<ApproximateMatch>| -> Vec<TextSize> {
+            matches.iter().map(|m| m.pos).collect()
+        };
+        assert_eq!(positions(hamming_search(text, sa.array(), b"nan", 0)), [2]);
+        assert_eq!(
+            positions(hamming_search(text, sa.array(), b"nan", 1)),
+            [0, 2]
+        );
+
+        // "bana", "anana" and "nana" are each one edit away.
+        let matches = edit_distance_search(text, sa.array(), b"bnana", 1);
+        assert_eq!(positions(matches.clone()), [0, 1, 2]);
+        let lengths: Vec<TextSize> = matches.iter().map(|m| m.length).collect();
+        assert_eq!(lengths, [4, 5, 4]);
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rand: usize = 17;
+        let mut random_text = |len: usize, alphabet: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % alphabet) as u8)
+                .collect()
+        };
+        for n in 0..40 {
+            let text = random_text(20 + n * 2, 2 + n % 3);
+            let sa = SaIsBuilder::new().build(&text);
+            let pattern = random_text(3 + n % 5, 2 + n % 3);
+            for k in 0..3 {
+                assert_eq!(
+                    hamming_search(&text, sa.array(), &pattern, k),
+                    naive_hamming(&text, &pattern, k)
+                );
+                assert_eq!(
+                    edit_distance_search(&text, sa.array(), &pattern, k),
+                    naive_edit(&text, &pattern, k)
+                );
+            }
+        }
+    }
+}
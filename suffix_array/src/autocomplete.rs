@@ -0,0 +1,189 @@
+// Top-k completions of a prefix, for query autocompletion.
+//
+// The completions of a prefix are the texts that follow its occurrences, up to a delimiter such
+// as a space or a newline. e.g. in "to be or not to be", the completions of "t" are "o" twice,
+// and "" once (in "not").
+//
+// The rows whose suffixes start with the prefix followed by a given completion and delimiter are
+// contiguous, and share (prefix + completion + 1) chars. Each group of rows is skipped with a
+// binary search over the LCP array, so the cost depends on the number of distinct completions
+// rather than on the number of occurrences of the prefix. Completions are cut at a maximum length,
+// which also bounds the scan for the delimiter in each group.
+
+use std::collections::HashMap;
+
+use super::lcp::{lcp_array, RangeMin};
+use super::search::find_range;
+use super::suffix_array::TextSize;
+
+// A text and its suffix array, indexed for autocompletion.
+pub struct AutocompleteIndex<'a> {
+    text: &'a [u8],
+    sa: &'a [TextSize],
+    lcp_min: RangeMin,
+}
+
+impl<'a> AutocompleteIndex<'a> {
+    pub fn new(text: &'a [u8], sa: &'a [TextSize]) -> AutocompleteIndex<'a> {
+        let lcp = lcp_array(text, sa);
+        AutocompleteIndex {
+            text,
+            sa,
+            lcp_min: RangeMin::new(&lcp),
+        }
+    }
+
+    // Returns the end of the group of rows starting at row start, whose suffixes share at least
+    // depth chars with it. The group ends at end at the latest.
+    fn group_end(&self, start: usize, end: usize, depth: TextSize) -> usize {
+        let (mut low, mut high) = (start + 1, end);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.lcp_min.min(start + 1, mid + 1) >= depth {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    // Returns all the distinct completions of the prefix up to any of the delimiters or the end
+    // of the text, with their number of occurrences, in no particular order. Completions longer
+    // than max_len are cut to their first max_len bytes.
+    pub fn completions(
+        &self,
+        prefix: &[u8],
+        max_len: usize,
+        delimiters: &[u8],
+    ) -> Vec<(&'a [u8], usize)> {
+        let range = find_range(self.text, self.sa, prefix);
+        let mut counts: HashMap<&'a [u8], usize> = HashMap::new();
+        let mut row = range.start;
+        while row < range.end {
+            let pos = self.sa[row] as usize;
+            let start = pos + prefix.len();
+            let limit = self.text.len().min(start.saturating_add(max_len));
+            let delimiter = self.text[start..limit]
+                .iter()
+                .position(|ch| delimiters.contains(ch))
+                .map(|len| start + len);
+            let end = delimiter.unwrap_or(limit);
+            let completion = &self.text[start..end];
+
+            // The group shares the completion and its delimiter, or the cut completion. At the end
+            // of the text, the suffix ends with the completion, and is alone in its group.
+            let next = match delimiter {
+                Some(end) => self.group_end(row, range.end, (end + 1 - pos) as TextSize),
+                None if end < self.text.len() => {
+                    self.group_end(row, range.end, (end - pos) as TextSize)
+                }
+                None => row + 1,
+            };
+            // The same completion may come with different delimiters.
+            *counts.entry(completion).or_insert(0) += next - row;
+            row = next;
+        }
+        counts.into_iter().collect()
+    }
+
+    // Returns the k most frequent completions of the prefix with their number of occurrences,
+    // by decreasing count, and then in lexicographic order.
+    pub fn top_k(
+        &self,
+        prefix: &[u8],
+        k: usize,
+        max_len: usize,
+        delimiters: &[u8],
+    ) -> Vec<(&'a [u8], usize)> {
+        let mut completions = self.completions(prefix, max_len, delimiters);
+        let order = |a: &(&[u8], usize), b: &(&[u8], usize)| b.1.cmp(&a.1).then(a.0.cmp(b.0));
+        if k < completions.len() {
+            if k == 0 {
+                return Vec::new();
+            }
+            completions.select_nth_unstable_by(k - 1, order);
+            completions.truncate(k);
+        }
+        completions.sort_unstable_by(order);
+        completions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    fn naive_completions<'a>(
+        text: &'a [u8],
+        prefix: &[u8],
+        max_len: usize,
+        delimiters: &[u8],
+    ) -> Vec<(&'a [u8], usize)> {
+        let mut counts: HashMap<&[u8], usize> = HashMap::new();
+        for pos in 0..text.len() {
+            if text[pos..].starts_with(prefix) {
+                let rest = &text[pos + prefix.len()..];
+                let len = rest
+                    .iter()
+                    .position(|ch| delimiters.contains(ch))
+                    .unwrap_or(rest.len())
+                    .min(max_len);
+                *counts.entry(&rest[..len]).or_insert(0) += 1;
+            }
+        }
+        let mut completions: Vec<(&[u8], usize)> = counts.into_iter().collect();
+        completions.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        completions
+    }
+
+    #[test]
+    fn to_be() {
+        let text = b"to be or not to be\nthat is the question";
+        let sa = SaIsBuilder::new().build(text);
+        let index = AutocompleteIndex::new(text, sa.array());
+        assert_eq!(
+            index.top_k(b"t", 3, 10, b" \n"),
+            [(&b""[..], 2), (b"o", 2), (b"hat", 1)]
+        );
+        assert_eq!(index.top_k(b"to ", 5, 10, b" \n"), [(&b"be"[..], 2)]);
+        assert_eq!(index.top_k(b"b", 5, 10, b" \n"), [(&b"e"[..], 2)]);
+        assert!(index.top_k(b"t", 0, 10, b" ").is_empty());
+        assert!(index.top_k(b"x", 3, 10, b" ").is_empty());
+
+        // Cut completions.
+        assert_eq!(
+            index.top_k(b"t", 3, 1, b" \n"),
+            [(&b""[..], 2), (b"h", 2), (b"o", 2)]
+        );
+        assert_eq!(index.top_k(b"qu", 1, 3, b""), [(&b"est"[..], 1)]);
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rand: usize = 41;
+        let words: [&[u8]; 8] = [b"a", b"ab", b"abc", b"b", b"ba", b"bab", b"c", b"abcab"];
+        let mut text: Vec<u8> = Vec::new();
+        for _ in 0..500 {
+            let r = testing::next_random(&mut rand);
+            text.extend_from_slice(words[r % words.len()]);
+            text.push([b' ', b' ', b'\n'][r % 3]);
+        }
+        text.extend_from_slice(b"abcabc");
+        let sa = SaIsBuilder::new().build(&text);
+        let index = AutocompleteIndex::new(&text, sa.array());
+        let prefixes: [&[u8]; 7] = [b"", b"a", b"ab", b"b", b"c", b" a", b"\nab"];
+        for prefix in prefixes {
+            for max_len in [0, 1, 2, 3, 4, 10, usize::MAX] {
+                let expected = naive_completions(&text, prefix, max_len, b" \n");
+                for k in [1, 3, expected.len() + 1] {
+                    let top_k = index.top_k(prefix, k, max_len, b" \n");
+                    assert_eq!(top_k, expected[..k.min(expected.len())]);
+                }
+            }
+        }
+    }
+}
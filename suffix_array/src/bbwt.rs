@@ -0,0 +1,223 @@
+// The bijective Burrows-Wheeler transform (BBWT).
+// See: Gil, Joseph Yossi; Scott, David Allen (2012). A Bijective String Sorting Transform.
+//
+// The text is split into its Lyndon factors w1 >= w2 >= ... >= wk. The transform is the last
+// char of every rotation of every factor, with the rotations sorted in omega-order, i.e. by
+// comparing their infinite repetitions. Unlike the BWT, no sentinel or primary index is needed.
+// e.g. the BBWT of "banana" = "b" "an" "an" "a" is "annbaa".
+//
+// The rotations are sorted by prefix doubling over the cyclic successor of each position
+// within its factor, which takes O(n log^2 n) time. Induced sorting can also sort them in
+// linear time (Bannai et al., 2021), but it classifies positions and recurses over the cyclic
+// successors within the factors, while sa_is.rs is built around the successor pos + 1 and a
+// sentinel at the end of a single text.
+
+use std::ops::Range;
+
+use super::suffix_array::TextSize;
+
+// Computes the Lyndon factorization with Duval's algorithm, in O(n) time.
+// Returns the ranges of the factors in text order, which are lexicographically non-increasing.
+// e.g. "banana" = "b" "an" "an" "a".
+pub fn lyndon_factorization(text: &[u8]) -> Vec<Range<usize>> {
+    let len = text.len();
+    let mut factors: Vec<Range<usize>> = Vec::new();
+    let mut start = 0;
+    while start < len {
+        // text[start..j] is a repetition of a Lyndon word of length (j - k), plus a prefix of it.
+        let mut k = start;
+        let mut j = start + 1;
+        while j < len && text[k] <= text[j] {
+            if text[k] < text[j] {
+                k = start;
+            } else {
+                k += 1;
+            }
+            j += 1;
+        }
+        let period = j - k;
+        while start <= k {
+            factors.push(start..start + period);
+            start += period;
+        }
+    }
+    factors
+}
+
+// Computes the BBWT of a text.
+pub fn bbwt(text: &[u8]) -> Vec<u8> {
+    let len = text.len();
+
+    // next[pos] = the following position within the same Lyndon factor, wrapping around.
+    let mut next: Vec<TextSize> = vec![0; len];
+    let mut prev: Vec<TextSize> = vec![0; len];
+    for factor in lyndon_factorization(text) {
+        for pos in factor.clone() {
+            let next_pos = if pos + 1 == factor.end {
+                factor.start
+            } else {
+                pos + 1
+            };
+            next[pos] = next_pos as TextSize;
+            prev[next_pos] = pos as TextSize;
+        }
+    }
+
+    // rank[pos] orders the rotations starting at pos by their first (step) chars.
+    let mut rank: Vec<TextSize> = text.iter().map(|&ch| ch as TextSize).collect();
+    let mut order: Vec<TextSize> = (0..len as TextSize).collect();
+    let mut num_ranks = 0;
+    let mut step = 1;
+    loop {
+        order.sort_unstable_by_key(|&pos| (rank[pos as usize], rank[next[pos as usize] as usize]));
+
+        // Re-rank by the first (2 * step) chars.
+        let mut new_rank: Vec<TextSize> = vec![0; len];
+        let mut count = 0;
+        for i in 0..len {
+            let pos = order[i] as usize;
+            if i > 0 {
+                let prev_pos = order[i - 1] as usize;
+                let key = (rank[pos], rank[next[pos] as usize]);
+                if key != (rank[prev_pos], rank[next[prev_pos] as usize]) {
+                    count += 1;
+                }
+            }
+            new_rank[pos] = count;
+        }
+        rank = new_rank;
+
+        // If the ranks are not refined further, they never will be. Equal rotations are
+        // rotations of equal factors, and they have the same last char.
+        if count + 1 == num_ranks || step >= len {
+            break;
+        }
+        num_ranks = count + 1;
+
+        // next[pos] = the position (2 * step) chars after pos.
+        next = next.iter().map(|&pos| next[pos as usize]).collect();
+        step *= 2;
+    }
+
+    order
+        .iter()
+        .map(|&pos| text[prev[pos as usize] as usize])
+        .collect()
+}
+
+// Reconstructs the text from its BBWT.
+pub fn inverse_bbwt(bbwt: &[u8]) -> Vec<u8> {
+    let len = bbwt.len();
+
+    // The first column of the sorted rotations is the sorted last column.
+    // psi[row] = the row of the rotation shifted left by one char.
+    let mut first_row: Vec<usize> = vec![0; 256];
+    for &ch in bbwt.iter() {
+        first_row[ch as usize] += 1;
+    }
+    let mut total = 0;
+    for count in first_row.iter_mut() {
+        let c = *count;
+        *count = total;
+        total += c;
+    }
+    let mut psi: Vec<TextSize> = vec![0; len];
+    for (row, &ch) in bbwt.iter().enumerate() {
+        psi[first_row[ch as usize]] = row as TextSize;
+        first_row[ch as usize] += 1;
+    }
+
+    // Each cycle of psi spells a Lyndon factor, when started from its smallest row.
+    // Visiting the rows in order gives the factors in increasing order.
+    let mut visited: Vec<bool> = vec![false; len];
+    let mut factors: Vec<Vec<u8>> = Vec::new();
+    let mut sorted_text: Vec<u8> = bbwt.to_vec();
+    sorted_text.sort_unstable();
+    for start in 0..len {
+        if visited[start] {
+            continue;
+        }
+        let mut factor: Vec<u8> = Vec::new();
+        let mut row = start;
+        while !visited[row] {
+            visited[row] = true;
+            factor.push(sorted_text[row]);
+            row = psi[row] as usize;
+        }
+        factors.push(factor);
+    }
+    factors.iter().rev().flatten().copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+
+    // Naively sorts the rotations of the Lyndon factors.
+    fn naive_bbwt(text: &[u8]) -> Vec<u8> {
+        let mut rotations: Vec<Vec<u8>> = Vec::new();
+        for factor in lyndon_factorization(text) {
+            let word = &text[factor];
+            for i in 0..word.len() {
+                rotations.push([&word[i..], &word[..i]].concat());
+            }
+        }
+        // Comparing the first (|a| + |b|) chars of two infinite repetitions is enough.
+        let omega =
+            |a: &Vec<u8>| -> Vec<u8> { a.iter().cycle().take(2 * text.len()).copied().collect() };
+        rotations.sort_by_key(omega);
+        rotations.iter().map(|r| *r.last().unwrap()).collect()
+    }
+
+    #[test]
+    fn factorization() {
+        fn factors(text: &str) -> Vec<&str> {
+            lyndon_factorization(text.as_bytes())
+                .into_iter()
+                .map(|r| &text[r])
+                .collect()
+        }
+        assert_eq!(factors("banana"), ["b", "an", "an", "a"]);
+        assert_eq!(factors("abaababaab"), ["ab", "aabab", "aab"]);
+        assert_eq!(factors("aaa"), ["a", "a", "a"]);
+        assert_eq!(factors("abcabd"), ["abcabd"]);
+        assert!(factors("").is_empty());
+    }
+
+    #[test]
+    fn banana() {
+        assert_eq!(bbwt(b"banana"), b"annbaa");
+        assert_eq!(inverse_bbwt(b"annbaa"), b"banana");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut test_strings: Vec<Vec<u8>> = [
+            "",
+            "a",
+            "aaaaaaaa",
+            "abababab",
+            "abcbabcba",
+            "mississippi",
+            "cabbage abc food abc vegetables",
+        ]
+        .iter()
+        .map(|s| s.as_bytes().to_vec())
+        .collect();
+
+        let mut rand: usize = 3;
+        for n in 0..100 {
+            let text: Vec<u8> = (0..1 + n % 50)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % (2 + n % 4)) as u8)
+                .collect();
+            test_strings.push(text);
+        }
+
+        for text in test_strings {
+            let transformed = bbwt(&text);
+            assert_eq!(transformed, naive_bbwt(&text));
+            assert_eq!(inverse_bbwt(&transformed), text);
+        }
+    }
+}
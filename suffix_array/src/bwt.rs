@@ -0,0 +1,147 @@
+// The Burrows-Wheeler transform (BWT), derived from a suffix array.
+//
+// The text is conceptually terminated by a unique sentinel "$" that is smaller than every
+// byte. The transform is the last column of the sorted rotations of (text + "$"), with the
+// sentinel dropped and its position recorded as the primary index.
+// e.g. the BWT of "banana" is "annbaa" with primary index 4 ("annb$aa").
+
+use super::suffix_array::{SuffixArrayBuilder, TextSize};
+
+pub struct Bwt {
+    // The transformed text, of the same length as the original text.
+    bwt: Vec<u8>,
+
+    // The row of (text + "$") rotations where the sentinel would be in the last column.
+    primary_index: TextSize,
+}
+
+impl Bwt {
+    // Computes the BWT of a text, using the given builder to build its suffix array.
+    pub fn new(sa_builder: &dyn SuffixArrayBuilder, text: &[u8]) -> Bwt {
+        if text.is_empty() {
+            return Bwt {
+                bwt: Vec::new(),
+                primary_index: 0,
+            };
+        }
+        let suffix_array = sa_builder.build(text);
+        Bwt::from_suffix_array(text, suffix_array.array())
+    }
+
+    // Computes the BWT of a text from its suffix array.
+    pub fn from_suffix_array(text: &[u8], sa: &[TextSize]) -> Bwt {
+        assert!(text.len() == sa.len());
+        let len = text.len();
+        let mut bwt: Vec<u8> = Vec::with_capacity(len);
+        let mut primary_index = 0;
+
+        if len > 0 {
+            // The first row is the rotation starting with the sentinel.
+            bwt.push(text[len - 1]);
+            for (i, &pos) in sa.iter().enumerate() {
+                if pos == 0 {
+                    primary_index = (i + 1) as TextSize;
+                } else {
+                    bwt.push(text[pos as usize - 1]);
+                }
+            }
+        }
+        Bwt { bwt, primary_index }
+    }
+
+    // Builds a Bwt from a previously computed transform.
+    pub fn from_parts(bwt: Vec<u8>, primary_index: TextSize) -> Bwt {
+        assert!(primary_index as usize <= bwt.len());
+        Bwt { bwt, primary_index }
+    }
+
+    // Returns the transformed text.
+    pub fn bwt(&self) -> &[u8] {
+        &self.bwt
+    }
+
+    // Returns the primary index.
+    pub fn primary_index(&self) -> TextSize {
+        self.primary_index
+    }
+
+    // Reconstructs the original text, or returns None if this is not a valid transform.
+    pub fn inverse(&self) -> Option<Vec<u8>> {
+        let len = self.bwt.len();
+        if len == 0 {
+            return Some(Vec::new());
+        }
+        let primary_index = self.primary_index as usize;
+
+        // Row i of the full last column (with the sentinel) is bwt[i], bwt[i - 1] or "$".
+        let last_char = |row: usize| -> Option<u8> {
+            match row.cmp(&primary_index) {
+                std::cmp::Ordering::Less => Some(self.bwt[row]),
+                std::cmp::Ordering::Equal => None,
+                std::cmp::Ordering::Greater => Some(self.bwt[row - 1]),
+            }
+        };
+
+        // first_row[ch] = the first row of the sorted rotations starting with ch.
+        // Row 0 is the rotation starting with the sentinel.
+        let mut first_row: Vec<usize> = vec![0; 256];
+        for &ch in self.bwt.iter() {
+            first_row[ch as usize] += 1;
+        }
+        let mut total = 1;
+        for count in first_row.iter_mut() {
+            let c = *count;
+            *count = total;
+            total += c;
+        }
+
+        // lf[row] = the row of the rotation that is shifted right by one char.
+        let mut lf: Vec<TextSize> = vec![0; len + 1];
+        for (row, lf_row) in lf.iter_mut().enumerate() {
+            if let Some(ch) = last_char(row) {
+                *lf_row = first_row[ch as usize] as TextSize;
+                first_row[ch as usize] += 1;
+            }
+        }
+
+        // Walk backwards from the rotation starting with the sentinel.
+        let mut text: Vec<u8> = vec![0; len];
+        let mut row = 0;
+        for i in (0..len).rev() {
+            text[i] = last_char(row)?;
+            row = lf[row] as usize;
+        }
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+
+    #[test]
+    fn banana() {
+        let bwt = Bwt::new(&SaIsBuilder::new(), b"banana");
+        assert_eq!(bwt.bwt(), b"annbaa");
+        assert_eq!(bwt.primary_index(), 4);
+        assert_eq!(bwt.inverse().unwrap(), b"banana");
+    }
+
+    #[test]
+    fn round_trip() {
+        let test_strings = [
+            "a",
+            "aaaaaaaa",
+            "abababab",
+            "abcbabcba",
+            "cabbage abc food abc vegetables",
+            "mississippi",
+        ];
+        let builder = SaIsBuilder::new();
+        for test_str in test_strings {
+            let bwt = Bwt::new(&builder, test_str.as_bytes());
+            assert_eq!(bwt.inverse().unwrap(), test_str.as_bytes());
+        }
+    }
+}
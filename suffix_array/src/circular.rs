@@ -0,0 +1,136 @@
+// Builds a circular suffix array, i.e. the sorted cyclic rotations of a text.
+// e.g. the circular suffix array of "abab" is [0, 2, 1, 3], since rotations "abab" (at 0) and
+// "abab" (at 2) are equal, followed by "baba" (at 1) and "baba" (at 3).
+//
+// Equal rotations occur when the text is a repetition of a shorter string. These are ordered
+// by position, the same as a stable sort of the rotations.
+
+use super::suffix_array::{SuffixArray, SuffixArrayBuilder, TextSize};
+
+pub struct CircularBuilder {
+    // Builds the suffix array of the doubled text.
+    sa_builder: Box<dyn SuffixArrayBuilder>,
+}
+
+impl CircularBuilder {
+    pub fn new(sa_builder: Box<dyn SuffixArrayBuilder>) -> CircularBuilder {
+        CircularBuilder { sa_builder }
+    }
+
+    // Returns the length of the shortest string whose repetition is the text.
+    // e.g. 2 for "abab", and 5 for "ababa".
+    fn primitive_root_len(text: &[u8]) -> usize {
+        // The KMP failure function of the whole text gives its smallest period.
+        let len = text.len();
+        let mut failure: Vec<usize> = vec![0; len + 1];
+        let mut k = 0;
+        for i in 1..len {
+            while k > 0 && text[i] != text[k] {
+                k = failure[k];
+            }
+            if text[i] == text[k] {
+                k += 1;
+            }
+            failure[i + 1] = k;
+        }
+        let period = len - failure[len];
+        if len.is_multiple_of(period) {
+            period
+        } else {
+            len
+        }
+    }
+
+    // Sorts the rotations of a text that is not a repetition, so no two rotations are equal.
+    fn build_primitive(&self, text: &[u8]) -> Vec<TextSize> {
+        // The order of the suffixes of (text + text) starting before len is the order of the
+        // rotations, since two different rotations differ within len chars.
+        let len = text.len();
+        let doubled_text: Vec<u8> = [text, text].concat();
+        let suffix_array = self.sa_builder.build(&doubled_text);
+        suffix_array
+            .iter()
+            .filter(|&pos| (pos as usize) < len)
+            .collect()
+    }
+}
+
+struct CircularSuffixArray {
+    sa: Vec<TextSize>,
+}
+
+impl SuffixArray for CircularSuffixArray {
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = TextSize> + 'a> {
+        Box::new(self.sa.iter().copied())
+    }
+
+    fn array(&self) -> &[TextSize] {
+        &self.sa
+    }
+}
+
+impl SuffixArrayBuilder for CircularBuilder {
+    fn build(&self, text: &[u8]) -> Box<dyn SuffixArray> {
+        if text.is_empty() {
+            return Box::new(CircularSuffixArray { sa: Vec::new() });
+        }
+
+        // Sort the rotations of the primitive root, then each rotation of the root is repeated
+        // at every (root_len) positions.
+        let len = text.len();
+        let root_len = Self::primitive_root_len(text);
+        let root_sa = self.build_primitive(&text[..root_len]);
+        let mut sa: Vec<TextSize> = Vec::with_capacity(len);
+        for &pos in root_sa.iter() {
+            for copy in (pos as usize..len).step_by(root_len) {
+                sa.push(copy as TextSize);
+            }
+        }
+        Box::new(CircularSuffixArray { sa })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::naive::NaiveBuilder;
+    use crate::sa_is::SaIsBuilder;
+
+    #[test]
+    fn periodic_strings() {
+        let builder = CircularBuilder::new(Box::new(SaIsBuilder::new()));
+        assert_eq!(builder.build(b"abab").array(), [0, 2, 1, 3]);
+        assert_eq!(builder.build(b"aaa").array(), [0, 1, 2]);
+        assert_eq!(builder.build(b"banana").array(), [5, 3, 1, 0, 4, 2]);
+    }
+
+    #[test]
+    fn matches_naive() {
+        let test_strings = [
+            "a",
+            "aaaaaaaa",
+            "aaaaaaab",
+            "abababab",
+            "abcabcabc",
+            "abcbabcba",
+            "abaababaabaab",
+            "cabbage abc food abc vegetables",
+        ];
+        let builders = [
+            CircularBuilder::new(Box::new(SaIsBuilder::new())),
+            CircularBuilder::new(Box::new(NaiveBuilder::new())),
+        ];
+        for builder in builders.iter() {
+            for test_str in test_strings {
+                let test_bytes = test_str.as_bytes();
+                let suffix_array = builder.build(test_bytes);
+                let sa_naive = testing::naive_circular_suffix_array(test_bytes);
+                assert!(testing::compare_suffix_arrays(
+                    &mut suffix_array.iter(),
+                    &mut sa_naive.iter().copied()
+                ));
+            }
+        }
+    }
+}
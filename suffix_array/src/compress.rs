@@ -0,0 +1,322 @@
+// A bzip2-style block compressor.
+//
+// The input is split into blocks, and each block goes through:
+//   BWT -> move-to-front -> zero run-length encoding -> Huffman coding.
+//
+// File format (integers are little endian u32):
+//   "SBWT" magic, block size.
+//   For each block: block length, CRC-32 of the block, BWT primary index, number of symbols,
+//   code length of each symbol (one byte each), number of encoded bytes, encoded bytes.
+
+use std::io::{Error, ErrorKind, Result};
+
+use super::bwt::Bwt;
+use super::errors::invalid_data;
+use super::huffman::{self, BitReader, BitWriter};
+use super::suffix_array::{SuffixArrayBuilder, TextSize};
+
+const MAGIC: &[u8; 4] = b"SBWT";
+
+// Same as the largest bzip2 block size.
+pub const DEFAULT_BLOCK_SIZE: usize = 900_000;
+
+// Symbols after the zero run-length encoding.
+// A run of zeros is written in bijective base 2 with digits RUN_A (1) and RUN_B (2).
+// Other MTF values v are written as v + 1.
+const RUN_A: usize = 0;
+const RUN_B: usize = 1;
+const NUM_SYMBOLS: usize = 257;
+
+pub struct BlockCompressor {
+    sa_builder: Box<dyn SuffixArrayBuilder>,
+    block_size: usize,
+}
+
+impl BlockCompressor {
+    // Creates a compressor that builds the BWT of each block with the given builder.
+    pub fn new(sa_builder: Box<dyn SuffixArrayBuilder>) -> BlockCompressor {
+        Self::with_block_size(sa_builder, DEFAULT_BLOCK_SIZE)
+    }
+
+    pub fn with_block_size(
+        sa_builder: Box<dyn SuffixArrayBuilder>,
+        block_size: usize,
+    ) -> BlockCompressor {
+        assert!(block_size > 0 && block_size < TextSize::MAX as usize / 2);
+        BlockCompressor {
+            sa_builder,
+            block_size,
+        }
+    }
+
+    // Compresses the input into the framed format.
+    pub fn compress(&self, input: &[u8]) -> Vec<u8> {
+        let mut output: Vec<u8> = Vec::new();
+        output.extend_from_slice(MAGIC);
+        write_u32(&mut output, self.block_size as u32);
+        for block in input.chunks(self.block_size) {
+            self.compress_block(block, &mut output);
+        }
+        output
+    }
+
+    fn compress_block(&self, block: &[u8], output: &mut Vec<u8>) {
+        let bwt = Bwt::new(self.sa_builder.as_ref(), block);
+        let symbols = zero_run_encode(&move_to_front(bwt.bwt()));
+
+        let mut freqs: Vec<u32> = vec![0; NUM_SYMBOLS];
+        for &s in symbols.iter() {
+            freqs[s as usize] += 1;
+        }
+        let lengths = huffman::code_lengths(&freqs);
+        let encoder = huffman::Encoder::new(&lengths);
+        let mut writer = BitWriter::new();
+        for &s in symbols.iter() {
+            encoder.write(&mut writer, s as usize);
+        }
+        let encoded = writer.finish();
+
+        write_u32(output, block.len() as u32);
+        write_u32(output, crc32(block));
+        write_u32(output, bwt.primary_index());
+        write_u32(output, symbols.len() as u32);
+        output.extend_from_slice(&lengths);
+        write_u32(output, encoded.len() as u32);
+        output.extend_from_slice(&encoded);
+    }
+}
+
+// Decompresses data produced by BlockCompressor.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = ByteReader { data, pos: 0 };
+    if reader.read_bytes(MAGIC.len())? != MAGIC {
+        return Err(invalid_data("bad magic"));
+    }
+    let block_size = reader.read_u32()? as usize;
+
+    let mut output: Vec<u8> = Vec::new();
+    while !reader.is_empty() {
+        let block_len = reader.read_u32()? as usize;
+        let checksum = reader.read_u32()?;
+        let primary_index = reader.read_u32()?;
+        let num_symbols = reader.read_u32()? as usize;
+        let lengths = reader.read_bytes(NUM_SYMBOLS)?;
+        let encoded_len = reader.read_u32()? as usize;
+        let encoded = reader.read_bytes(encoded_len)?;
+        if block_len > block_size
+            || primary_index as usize > block_len
+            || num_symbols > block_len
+            || lengths.iter().any(|&l| l > huffman::MAX_CODE_LENGTH)
+        {
+            return Err(invalid_data("bad block header"));
+        }
+
+        let decoder = huffman::Decoder::new(lengths);
+        let mut bits = BitReader::new(encoded);
+        let mut symbols: Vec<u16> = Vec::with_capacity(num_symbols);
+        for _ in 0..num_symbols {
+            let s = decoder
+                .read(&mut bits)
+                .ok_or_else(|| invalid_data("bad huffman code"))?;
+            symbols.push(s as u16);
+        }
+
+        let mtf = zero_run_decode(&symbols, block_len)?;
+        let block = Bwt::from_parts(inverse_move_to_front(&mtf), primary_index)
+            .inverse()
+            .ok_or_else(|| invalid_data("bad transform"))?;
+        if crc32(&block) != checksum {
+            return Err(invalid_data("block checksum mismatch"));
+        }
+        output.extend_from_slice(&block);
+    }
+    Ok(output)
+}
+
+fn write_u32(output: &mut Vec<u8>, value: u32) {
+    output.extend_from_slice(&value.to_le_bytes());
+}
+
+// Reads the framed format.
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos == self.data.len()
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() - self.pos < len {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "truncated input"));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+// Replaces each byte by its index in a list of recently used bytes, then moves it to the front.
+fn move_to_front(input: &[u8]) -> Vec<u8> {
+    let mut order: Vec<u8> = (0..=255).collect();
+    let mut output: Vec<u8> = Vec::with_capacity(input.len());
+    for &ch in input.iter() {
+        let index = order.iter().position(|&c| c == ch).unwrap();
+        order.copy_within(0..index, 1);
+        order[0] = ch;
+        output.push(index as u8);
+    }
+    output
+}
+
+fn inverse_move_to_front(input: &[u8]) -> Vec<u8> {
+    let mut order: Vec<u8> = (0..=255).collect();
+    let mut output: Vec<u8> = Vec::with_capacity(input.len());
+    for &index in input.iter() {
+        let ch = order[index as usize];
+        order.copy_within(0..index as usize, 1);
+        order[0] = ch;
+        output.push(ch);
+    }
+    output
+}
+
+// Encodes runs of zeros, which dominate the MTF output.
+fn zero_run_encode(input: &[u8]) -> Vec<u16> {
+    fn flush_run(run: &mut usize, output: &mut Vec<u16>) {
+        while *run > 0 {
+            *run -= 1;
+            output.push(if *run & 1 == 0 { RUN_A } else { RUN_B } as u16);
+            *run >>= 1;
+        }
+    }
+
+    let mut output: Vec<u16> = Vec::with_capacity(input.len());
+    let mut run = 0;
+    for &v in input.iter() {
+        if v == 0 {
+            run += 1;
+        } else {
+            flush_run(&mut run, &mut output);
+            output.push(v as u16 + 1);
+        }
+    }
+    flush_run(&mut run, &mut output);
+    output
+}
+
+fn zero_run_decode(input: &[u16], len: usize) -> Result<Vec<u8>> {
+    let mut output: Vec<u8> = Vec::with_capacity(len);
+    let mut run = 0;
+    let mut digit = 1;
+    for &s in input.iter() {
+        let s = s as usize;
+        if s == RUN_A || s == RUN_B {
+            run += digit * (s + 1);
+            digit <<= 1;
+            if run > len {
+                return Err(invalid_data("bad run length"));
+            }
+        } else {
+            output.resize(output.len() + run, 0);
+            run = 0;
+            digit = 1;
+            output.push((s - 1) as u8);
+        }
+    }
+    output.resize(output.len() + run, 0);
+    if output.len() != len {
+        return Err(invalid_data("bad block length"));
+    }
+    Ok(output)
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table: [u32; 256] = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            bit += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+// CRC-32 (IEEE 802.3), as used by gzip and zip.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data.iter() {
+        crc = CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naive::NaiveBuilder;
+    use crate::sa_is::SaIsBuilder;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut long_text: Vec<u8> = Vec::new();
+        for i in 0..1000u32 {
+            long_text.extend_from_slice(format!("line {} of {}\n", i % 97, i % 13).as_bytes());
+        }
+        let inputs: [&[u8]; 5] = [
+            b"",
+            b"a",
+            b"aaaaaaaaaaaaaaaaaaaa",
+            b"banana bandana",
+            &long_text,
+        ];
+
+        for block_size in [1, 7, 1000, DEFAULT_BLOCK_SIZE] {
+            let compressor =
+                BlockCompressor::with_block_size(Box::new(SaIsBuilder::new()), block_size);
+            for input in inputs {
+                let compressed = compressor.compress(input);
+                assert_eq!(decompress(&compressed).unwrap(), input);
+            }
+        }
+
+        let compressor = BlockCompressor::new(Box::new(NaiveBuilder::new()));
+        let compressed = compressor.compress(&long_text);
+        assert!(compressed.len() < long_text.len() / 4);
+        assert_eq!(decompress(&compressed).unwrap(), long_text);
+    }
+
+    #[test]
+    fn corrupted_input() {
+        let compressor = BlockCompressor::new(Box::new(SaIsBuilder::new()));
+        let mut compressed = compressor.compress(b"cabbage abc food abc vegetables");
+        assert!(decompress(&compressed[..compressed.len() - 1]).is_err());
+
+        // Flip a bit in the checksum.
+        compressed[12] ^= 1;
+        assert!(decompress(&compressed).is_err());
+    }
+}
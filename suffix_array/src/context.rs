@@ -0,0 +1,160 @@
+// Keyword in context (KWIC): the hits of a pattern with the text around them.
+//
+// Each hit comes with its line and column, and with up to a given number of bytes of context on
+// each side. The context stops at line boundaries, so that each hit displays on one line.
+
+use super::records::RecordIndex;
+use super::search::find_range;
+use super::suffix_array::TextSize;
+
+// The order of the hits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HitOrder {
+    // By text position.
+    Text,
+
+    // By suffix array row, which sorts the hits by their right context.
+    Suffix,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hit<'a> {
+    // Start of the hit in the text.
+    pub pos: TextSize,
+
+    // Line of the hit, and its byte offset in the line. Both start from 1.
+    pub line: usize,
+    pub column: usize,
+
+    pub left: &'a [u8],
+    pub keyword: &'a [u8],
+    pub right: &'a [u8],
+}
+
+// Start positions of the lines of a text.
+pub struct LineIndex {
+    records: RecordIndex,
+}
+
+impl LineIndex {
+    pub fn new(text: &[u8]) -> LineIndex {
+        LineIndex {
+            records: RecordIndex::lines(text),
+        }
+    }
+
+    // Returns the (line, column) of a text position. Both start from 1. The position must be
+    // within the text.
+    pub fn line_column(&self, pos: usize) -> (usize, usize) {
+        let position = self
+            .records
+            .position(pos)
+            .expect("position past the end of the text");
+        (position.record + 1, position.offset + 1)
+    }
+
+    // Returns the start of the line of a text position.
+    pub fn line_start(&self, pos: usize) -> usize {
+        self.records.range(self.line_column(pos).0 - 1).start
+    }
+}
+
+// Returns the hits of the pattern with up to left_len and right_len bytes of context.
+pub fn keyword_in_context<'a>(
+    text: &'a [u8],
+    sa: &[TextSize],
+    pattern: &[u8],
+    left_len: usize,
+    right_len: usize,
+    order: HitOrder,
+) -> Vec<Hit<'a>> {
+    let lines = LineIndex::new(text);
+    let mut positions: Vec<TextSize> = sa[find_range(text, sa, pattern)].to_vec();
+    if order == HitOrder::Text {
+        positions.sort_unstable();
+    }
+
+    positions
+        .into_iter()
+        .map(|pos| {
+            let start = pos as usize;
+            let end = start + pattern.len();
+            let (line, column) = lines.line_column(start);
+            let left_start = start.saturating_sub(left_len).max(lines.line_start(start));
+            let right_end = text[end..]
+                .iter()
+                .take(right_len)
+                .position(|&ch| ch == b'\n')
+                .map_or((end + right_len).min(text.len()), |len| end + len);
+            Hit {
+                pos,
+                line,
+                column,
+                left: &text[left_start..start],
+                keyword: &text[start..end],
+                right: &text[end..right_end],
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    #[test]
+    fn lines() {
+        let lines = LineIndex::new(b"ab\n\ncd\n");
+        assert_eq!(lines.line_column(0), (1, 1));
+        assert_eq!(lines.line_column(2), (1, 3));
+        assert_eq!(lines.line_column(3), (2, 1));
+        assert_eq!(lines.line_column(5), (3, 2));
+        assert_eq!(lines.line_start(6), 4);
+    }
+
+    #[test]
+    fn hits() {
+        let text = b"to be or not\nto be, that is\nthe question";
+        let sa = SaIsBuilder::new().build(text);
+        let hits = keyword_in_context(text, sa.array(), b"to be", 3, 6, HitOrder::Text);
+        assert_eq!(
+            hits,
+            [
+                Hit {
+                    pos: 0,
+                    line: 1,
+                    column: 1,
+                    left: b"",
+                    keyword: b"to be",
+                    right: b" or no",
+                },
+                Hit {
+                    pos: 13,
+                    line: 2,
+                    column: 1,
+                    left: b"",
+                    keyword: b"to be",
+                    right: b", that",
+                },
+            ]
+        );
+
+        let hits = keyword_in_context(text, sa.array(), b"t", 4, 2, HitOrder::Text);
+        let positions: Vec<(usize, usize)> = hits.iter().map(|h| (h.line, h.column)).collect();
+        assert_eq!(
+            positions,
+            [(1, 1), (1, 12), (2, 1), (2, 8), (2, 11), (3, 1), (3, 9)]
+        );
+        assert_eq!(hits[1].left, b"r no");
+        assert_eq!(hits[1].right, b"");
+        assert_eq!(hits[6].right, b"io");
+
+        // By suffix order, hits are sorted by the text from the keyword on.
+        let hits = keyword_in_context(text, sa.array(), b"t", 0, 10, HitOrder::Suffix);
+        let rights: Vec<&[u8]> = hits.iter().map(|h| h.right).collect();
+        assert!(rights.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(hits.len(), 7);
+    }
+}
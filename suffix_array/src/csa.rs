@@ -0,0 +1,362 @@
+// Compressed suffix array, with the Ψ function (Grossi-Vitter, Sadakane).
+//
+// The rows are those of the suffix array of text + '$', where '$' is smaller than every byte, so
+// row 0 is the empty suffix, and row r + 1 is rank r of the suffix array. Ψ(r) is the row of the
+// suffix that follows the suffix of row r, and Ψ(0) is the row of the whole text.
+//
+// Ψ is increasing over the rows whose suffixes start with the same byte, so it is stored as the
+// differences between consecutive rows, as varints, with an absolute value every PSI_BLOCK rows.
+// With the first byte of each row, given by the counts of the bytes, Ψ replaces the text:
+// a suffix is read by following Ψ from its row.
+//
+// The suffix array is sampled at the text positions that are multiples of the sample rate s, and
+// the rows of the samples are marked in a bit vector. lookup() follows Ψ to the next marked row,
+// in at most s steps. The inverse suffix array is sampled at the same positions, and inverse()
+// follows Ψ from the previous sample. A larger s takes less space, and more time.
+
+use std::ops::Range;
+
+use super::bitvector::BitVector;
+use super::suffix_array::TextSize;
+
+const PSI_BLOCK: usize = 32;
+
+fn write_varint(mut value: u64, output: &mut Vec<u8>) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> u64 {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+// Maps deltas of small absolute value to small varints: 0, -1, 1, -2, ... to 0, 1, 2, 3, ...
+fn zigzag(delta: i64) -> u64 {
+    ((delta << 1) ^ (delta >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+pub struct CompressedSuffixArray {
+    len: usize,
+    sample_rate: usize,
+
+    // First row of the suffixes that start with each byte, and the end of the rows.
+    starts: Vec<TextSize>,
+
+    // Ψ of every PSI_BLOCK-th row, and the offset of the differences that follow it.
+    psi_samples: Vec<TextSize>,
+    psi_offsets: Vec<TextSize>,
+
+    // Zigzag encoded differences of Ψ.
+    psi_deltas: Vec<u8>,
+
+    // Rows of the positions that are multiples of the sample rate, or the end of the text.
+    marks: BitVector,
+
+    // Positions of the marked rows, in row order.
+    sa_samples: Vec<TextSize>,
+
+    // Row of each multiple of the sample rate.
+    isa_samples: Vec<TextSize>,
+}
+
+impl CompressedSuffixArray {
+    pub fn new(text: &[u8], sa: &[TextSize], sample_rate: usize) -> CompressedSuffixArray {
+        assert_eq!(text.len(), sa.len());
+        assert!(sample_rate > 0);
+        let n = text.len();
+
+        let mut counts: Vec<TextSize> = vec![0; 256];
+        for &ch in text {
+            counts[ch as usize] += 1;
+        }
+        let mut starts: Vec<TextSize> = Vec::with_capacity(257);
+        let mut start = 1;
+        for count in counts {
+            starts.push(start);
+            start += count;
+        }
+        starts.push(start);
+
+        // Row of each position, with the empty suffix at row 0.
+        let mut rows: Vec<TextSize> = vec![0; n + 1];
+        for (rank, &pos) in sa.iter().enumerate() {
+            rows[pos as usize] = rank as TextSize + 1;
+        }
+        let position = |row: usize| if row == 0 { n } else { sa[row - 1] as usize };
+
+        let mut psi_samples: Vec<TextSize> = Vec::new();
+        let mut psi_offsets: Vec<TextSize> = Vec::new();
+        let mut psi_deltas: Vec<u8> = Vec::new();
+        let mut previous: i64 = 0;
+        for row in 0..=n {
+            let pos = position(row);
+            let psi = rows[if pos == n { 0 } else { pos + 1 }];
+            if row % PSI_BLOCK == 0 {
+                psi_samples.push(psi);
+                psi_offsets.push(psi_deltas.len() as TextSize);
+            } else {
+                let delta = psi as i64 - previous;
+                write_varint(zigzag(delta), &mut psi_deltas);
+            }
+            previous = psi as i64;
+        }
+
+        let is_sampled = |pos: usize| pos.is_multiple_of(sample_rate) || pos == n;
+        let marks: BitVector = (0..=n).map(|row| is_sampled(position(row))).collect();
+        let sa_samples: Vec<TextSize> = (0..=n)
+            .filter(|&row| marks.get(row))
+            .map(|row| position(row) as TextSize)
+            .collect();
+        let isa_samples: Vec<TextSize> = (0..n).step_by(sample_rate).map(|pos| rows[pos]).collect();
+
+        CompressedSuffixArray {
+            len: n,
+            sample_rate,
+            starts,
+            psi_samples,
+            psi_offsets,
+            psi_deltas,
+            marks,
+            sa_samples,
+            isa_samples,
+        }
+    }
+
+    // Length of the text.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    fn psi(&self, row: usize) -> usize {
+        let block = row / PSI_BLOCK;
+        let mut psi = self.psi_samples[block] as i64;
+        let mut offset = self.psi_offsets[block] as usize;
+        for _ in 0..row % PSI_BLOCK {
+            psi += unzigzag(read_varint(&self.psi_deltas, &mut offset));
+        }
+        psi as usize
+    }
+
+    // First byte of the suffix of a row, other than row 0.
+    fn first_byte(&self, row: usize) -> u8 {
+        (self.starts.partition_point(|&start| start as usize <= row) - 1) as u8
+    }
+
+    // Returns the position of the suffix of the given rank, i.e. SA[rank].
+    pub fn lookup(&self, rank: usize) -> TextSize {
+        assert!(rank < self.len);
+        let mut row = rank + 1;
+        let mut steps = 0;
+        while !self.marks.get(row) {
+            row = self.psi(row);
+            steps += 1;
+        }
+        self.sa_samples[self.marks.rank1(row)] - steps
+    }
+
+    fn row(&self, pos: usize) -> usize {
+        let sample = pos / self.sample_rate;
+        let mut row = self.isa_samples[sample] as usize;
+        for _ in sample * self.sample_rate..pos {
+            row = self.psi(row);
+        }
+        row
+    }
+
+    // Returns the rank of the suffix at the given position, i.e. ISA[pos].
+    pub fn inverse(&self, pos: usize) -> usize {
+        assert!(pos < self.len);
+        self.row(pos) - 1
+    }
+
+    // Returns text[pos..pos + len], or up to the end of the text.
+    pub fn extract(&self, pos: usize, len: usize) -> Vec<u8> {
+        assert!(pos <= self.len);
+        let len = len.min(self.len - pos);
+        if len == 0 {
+            return Vec::new();
+        }
+        let mut row = self.row(pos);
+        let mut bytes: Vec<u8> = Vec::with_capacity(len);
+        for _ in 0..len {
+            bytes.push(self.first_byte(row));
+            row = self.psi(row);
+        }
+        bytes
+    }
+
+    // Compares the prefix of the suffix of a row with the pattern.
+    fn compare(&self, mut row: usize, pattern: &[u8]) -> std::cmp::Ordering {
+        for &ch in pattern {
+            if row == 0 {
+                return std::cmp::Ordering::Less;
+            }
+            let byte = self.first_byte(row);
+            if byte != ch {
+                return byte.cmp(&ch);
+            }
+            row = self.psi(row);
+        }
+        std::cmp::Ordering::Equal
+    }
+
+    // Returns the range of ranks whose suffixes start with the pattern.
+    pub fn find_range(&self, pattern: &[u8]) -> Range<usize> {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.compare(mid + 1, pattern).is_lt() {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let start = low;
+        high = self.len;
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.compare(mid + 1, pattern).is_le() {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        start..low
+    }
+
+    // Returns the positions of all occurrences of the pattern, in text order.
+    pub fn locate(&self, pattern: &[u8]) -> Vec<TextSize> {
+        let mut positions: Vec<TextSize> = self
+            .find_range(pattern)
+            .map(|rank| self.lookup(rank))
+            .collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    // Bytes used by the structure.
+    pub fn size_in_bytes(&self) -> usize {
+        let words = self.starts.len()
+            + self.psi_samples.len()
+            + self.psi_offsets.len()
+            + self.sa_samples.len()
+            + self.isa_samples.len();
+        words * std::mem::size_of::<TextSize>() + self.psi_deltas.len() + self.marks.size_in_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::{inverse_suffix_array, SuffixArrayBuilder};
+
+    fn check(text: &[u8], sample_rate: usize) {
+        let sa = SaIsBuilder::new().build(text);
+        let isa = inverse_suffix_array(sa.array());
+        let csa = CompressedSuffixArray::new(text, sa.array(), sample_rate);
+        assert_eq!(csa.len(), text.len());
+        for (rank, pos) in sa.iter().enumerate() {
+            assert_eq!(csa.lookup(rank), pos);
+            assert_eq!(csa.inverse(pos as usize), isa[pos as usize] as usize);
+        }
+        assert_eq!(csa.extract(0, text.len()), text);
+        for len in 0..5 {
+            let start = text.len() / 3;
+            let pattern = &text[start..(start + len).min(text.len())];
+            assert_eq!(csa.find_range(pattern), sa.find_range(text, pattern));
+            assert_eq!(csa.locate(pattern), sa.locate(text, pattern));
+        }
+    }
+
+    #[test]
+    fn varints() {
+        let deltas = [
+            0,
+            1,
+            -1,
+            63,
+            -64,
+            1 << 31,
+            -(1 << 31),
+            u32::MAX as i64,
+            -(u32::MAX as i64),
+        ];
+        let mut data: Vec<u8> = Vec::new();
+        for &delta in &deltas {
+            write_varint(zigzag(delta), &mut data);
+        }
+        let mut pos = 0;
+        for &delta in &deltas {
+            assert_eq!(unzigzag(read_varint(&data, &mut pos)), delta);
+        }
+        assert_eq!(pos, data.len());
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+    }
+
+    #[test]
+    fn banana() {
+        let text = b"banana";
+        let csa = CompressedSuffixArray::new(text, SaIsBuilder::new().build(text).array(), 2);
+        assert_eq!(csa.locate(b"ana"), [1, 3]);
+        assert_eq!(csa.find_range(b"n"), 4..6);
+        assert!(csa.locate(b"nab").is_empty());
+        assert!(csa.locate(b"bananas").is_empty());
+        assert_eq!(csa.extract(2, 10), b"nana");
+        assert!(csa.extract(6, 1).is_empty());
+        for sample_rate in 1..8 {
+            check(text, sample_rate);
+        }
+    }
+
+    #[test]
+    fn random_texts() {
+        let mut rand: usize = 41;
+        for n in 1..60 {
+            let text: Vec<u8> = (0..n * 7)
+                .map(|_| [b'a', b'b', b'c', 0, 255][testing::next_random(&mut rand) % (2 + n % 4)])
+                .collect();
+            check(&text, 1 + n % 9);
+        }
+    }
+
+    #[test]
+    fn sample_rate_trades_space() {
+        let text: Vec<u8> = (0..10000)
+            .map(|i| b"abracadabra"[i % 11] ^ (i / 97) as u8)
+            .collect();
+        let sa = SaIsBuilder::new().build(&text);
+        let small = CompressedSuffixArray::new(&text, sa.array(), 4);
+        let large = CompressedSuffixArray::new(&text, sa.array(), 64);
+        assert!(large.size_in_bytes() < small.size_in_bytes());
+        assert!(large.size_in_bytes() < text.len() * std::mem::size_of::<TextSize>());
+        assert_eq!(large.locate(b"cad"), small.locate(b"cad"));
+    }
+}
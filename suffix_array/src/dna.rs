@@ -0,0 +1,548 @@
+// Packed DNA texts, with 2 bits per base.
+//
+// The bases A, C, G and T are packed 32 to a u64. Any other byte is read as N, and kept in a
+// list of runs of N positions, since N mostly comes in long runs for unsequenced regions.
+// Lowercase (soft-masked) bases are read as uppercase. The sequences of several records are
+// concatenated, and the starts of the records are kept so that hits do not span two records.
+//
+// The suffix array is built directly over the packed text, with the 5 letter alphabet
+// A < C < G < N < T, which is the same order as for the ASCII bases.
+//
+// Patterns are searched over the packed text, with the bases read back as A, C, G, T or N, so the
+// text is never unpacked.
+//
+// A pattern is found on the reverse strand by searching for its reverse complement on the forward
+// strand, so the text is only indexed once.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+
+use super::fasta::read_sequences;
+use super::sa_is::{SaIsBuilder, Text};
+use super::search::locate;
+use super::suffix_array::{SuffixArray, TextSize};
+
+const BASES: [u8; 4] = [b'A', b'C', b'G', b'T'];
+
+// Ranks of A, C, G, T and N in the alphabet of the suffix array.
+const RANKS: [u32; 4] = [0, 1, 2, 4];
+const N_RANK: u32 = 3;
+
+fn base_code(ch: u8) -> Option<u64> {
+    match ch.to_ascii_uppercase() {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+pub struct PackedDna {
+    words: Vec<u64>,
+    len: usize,
+
+    // Runs of N positions, in increasing order.
+    exceptions: Vec<Range<TextSize>>,
+
+    // Starts of the records after the first one, in increasing order.
+    record_starts: Vec<TextSize>,
+}
+
+impl PackedDna {
+    pub fn new(sequence: &[u8]) -> PackedDna {
+        let mut dna = PackedDna {
+            words: Vec::with_capacity(sequence.len().div_ceil(32)),
+            len: 0,
+            exceptions: Vec::new(),
+            record_starts: Vec::new(),
+        };
+        dna.extend(sequence);
+        dna
+    }
+
+    // Reads the sequences of a FASTA or FASTQ file, and concatenates them as separate records.
+    pub fn from_fasta(fasta: &[u8]) -> std::io::Result<PackedDna> {
+        let mut dna = PackedDna::new(b"");
+        for record in read_sequences(fasta)? {
+            dna.start_record();
+            dna.extend(&record.sequence);
+        }
+        Ok(dna)
+    }
+
+    // Starts a new record at the end of the text. Empty records are not kept.
+    pub fn start_record(&mut self) {
+        let end = self.len as TextSize;
+        if end > 0 && self.record_starts.last() != Some(&end) {
+            self.record_starts.push(end);
+        }
+    }
+
+    // Number of records.
+    pub fn records(&self) -> usize {
+        self.record_starts.len() + !self.is_empty() as usize
+    }
+
+    // Whether text[pos..pos + len] spans two records.
+    fn spans_records(&self, pos: usize, len: usize) -> bool {
+        let i = self
+            .record_starts
+            .partition_point(|&start| start as usize <= pos);
+        self.record_starts
+            .get(i)
+            .is_some_and(|&start| (start as usize) < pos + len)
+    }
+
+    // Appends bases.
+    pub fn extend(&mut self, sequence: &[u8]) {
+        for &ch in sequence {
+            let code = match base_code(ch) {
+                Some(code) => code,
+                None => {
+                    let pos = self.len as TextSize;
+                    match self.exceptions.last_mut() {
+                        Some(run) if run.end == pos => run.end += 1,
+                        _ => self.exceptions.push(pos..pos + 1),
+                    }
+                    0
+                }
+            };
+            if self.len.is_multiple_of(32) {
+                self.words.push(0);
+            }
+            *self.words.last_mut().unwrap() |= code << (2 * (self.len % 32));
+            self.len += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn is_exception(&self, pos: usize) -> bool {
+        let pos = pos as TextSize;
+        let i = self.exceptions.partition_point(|run| run.end <= pos);
+        self.exceptions.get(i).is_some_and(|run| run.start <= pos)
+    }
+
+    fn code(&self, pos: usize) -> usize {
+        ((self.words[pos / 32] >> (2 * (pos % 32))) & 3) as usize
+    }
+
+    // Returns the base at pos, as A, C, G, T or N.
+    pub fn get(&self, pos: usize) -> u8 {
+        assert!(pos < self.len);
+        if self.is_exception(pos) {
+            b'N'
+        } else {
+            BASES[self.code(pos)]
+        }
+    }
+
+    // Unpacks the bases.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        (0..self.len).map(|pos| self.get(pos)).collect()
+    }
+
+    // Compares the suffix at pos with the pattern, which is read as it would be packed.
+    fn compare(&self, pos: usize, pattern: &[u8]) -> Ordering {
+        for (i, &ch) in pattern.iter().enumerate() {
+            if pos + i == self.len {
+                return Ordering::Less;
+            }
+            let ch = base_code(ch).map_or(b'N', |code| BASES[code as usize]);
+            match self.get(pos + i).cmp(&ch) {
+                Ordering::Equal => {}
+                order => return order,
+            }
+        }
+        Ordering::Equal
+    }
+
+    // Returns the range of rows of the suffix array whose suffixes start with the pattern. The
+    // pattern is packed like the text, so it ignores case, and any byte other than ACGT is N.
+    // The rows include the occurrences that span records.
+    pub fn find_range(&self, sa: &[TextSize], pattern: &[u8]) -> Range<usize> {
+        let start = sa.partition_point(|&pos| self.compare(pos as usize, pattern).is_lt());
+        let end =
+            start + sa[start..].partition_point(|&pos| self.compare(pos as usize, pattern).is_eq());
+        start..end
+    }
+
+    // Returns the positions of the occurrences of the pattern within records, in text order.
+    pub fn locate(&self, sa: &[TextSize], pattern: &[u8]) -> Vec<TextSize> {
+        let mut positions: Vec<TextSize> = sa[self.find_range(sa, pattern)]
+            .iter()
+            .copied()
+            .filter(|&pos| !self.spans_records(pos as usize, pattern.len()))
+            .collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    // Returns the occurrences of the pattern on both strands, in text order, like
+    // locate_both_strands over the suffix array of the packed text.
+    pub fn locate_both_strands(&self, sa: &[TextSize], pattern: &[u8]) -> Vec<StrandHit> {
+        merge_strands(
+            self.locate(sa, pattern),
+            self.locate(sa, &reverse_complement(pattern)),
+        )
+    }
+
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport {
+            bases: self.len,
+            packed_bytes: self.words.len() * 8
+                + self.exceptions.len() * std::mem::size_of::<Range<TextSize>>()
+                + self.record_starts.len() * std::mem::size_of::<TextSize>(),
+            exception_runs: self.exceptions.len(),
+            suffix_array_bytes: self.len * std::mem::size_of::<TextSize>(),
+        }
+    }
+}
+
+impl Text for PackedDna {
+    fn len(&self) -> TextSize {
+        self.len as TextSize
+    }
+
+    fn char_at(&self, index: TextSize) -> u32 {
+        let pos = index as usize;
+        if !self.exceptions.is_empty() && self.is_exception(pos) {
+            N_RANK
+        } else {
+            RANKS[self.code(pos)]
+        }
+    }
+
+    fn substring(&self, start: TextSize, end: TextSize) -> String {
+        (start..end)
+            .map(|pos| self.get(pos as usize) as char)
+            .collect()
+    }
+}
+
+impl fmt::Display for PackedDna {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.substring(0, self.len as TextSize))
+    }
+}
+
+// Memory used by a packed DNA text, compared with one byte per base.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryReport {
+    pub bases: usize,
+
+    // Bytes of the packed bases and the N runs.
+    pub packed_bytes: usize,
+
+    pub exception_runs: usize,
+
+    // Bytes of the suffix array, which are the same either way.
+    pub suffix_array_bytes: usize,
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Bases: {}", self.bases)?;
+        writeln!(f, "Unpacked text: {} bytes", self.bases)?;
+        writeln!(
+            f,
+            "Packed text: {} bytes ({:.1}%), {} N runs",
+            self.packed_bytes,
+            100.0 * self.packed_bytes as f64 / self.bases.max(1) as f64,
+            self.exception_runs
+        )?;
+        write!(f, "Suffix array: {} bytes", self.suffix_array_bytes)
+    }
+}
+
+// Complement of a base, including the IUPAC ambiguity codes. Case is kept, and other bytes are
+// their own complement.
+fn complement(ch: u8) -> u8 {
+    let upper = match ch.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' | b'U' => b'A',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        _ => return ch,
+    };
+    if ch.is_ascii_lowercase() {
+        upper.to_ascii_lowercase()
+    } else {
+        upper
+    }
+}
+
+pub fn reverse_complement(sequence: &[u8]) -> Vec<u8> {
+    sequence.iter().rev().map(|&ch| complement(ch)).collect()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+impl fmt::Display for Strand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strand::Forward => write!(f, "+"),
+            Strand::Reverse => write!(f, "-"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StrandHit {
+    // Start of the hit on the forward strand.
+    pub pos: usize,
+    pub strand: Strand,
+}
+
+fn merge_strands(forward: Vec<TextSize>, reverse: Vec<TextSize>) -> Vec<StrandHit> {
+    let hits = |positions: Vec<TextSize>, strand: Strand| {
+        positions.into_iter().map(move |pos| StrandHit {
+            pos: pos as usize,
+            strand,
+        })
+    };
+    let mut hits_both: Vec<StrandHit> = hits(forward, Strand::Forward)
+        .chain(hits(reverse, Strand::Reverse))
+        .collect();
+    hits_both.sort_unstable();
+    hits_both
+}
+
+// Returns the occurrences of the pattern on both strands of the text, in text order.
+// A reverse strand hit at pos means the reverse complement of the pattern occurs at
+// text[pos..pos + pattern.len()]. A pattern that is its own reverse complement is reported on
+// both strands. The suffix array must be in byte order, as built by SaIsBuilder; for the
+// suffix array of a packed text, use PackedDna::locate_both_strands.
+pub fn locate_both_strands(text: &[u8], sa: &[TextSize], pattern: &[u8]) -> Vec<StrandHit> {
+    merge_strands(
+        locate(text, sa, pattern),
+        locate(text, sa, &reverse_complement(pattern)),
+    )
+}
+
+// Builds suffix arrays over packed DNA texts.
+pub struct DnaBuilder {}
+
+impl DnaBuilder {
+    pub fn new() -> DnaBuilder {
+        DnaBuilder {}
+    }
+
+    // The suffixes are in the order of the packed text, so the suffix array must be searched
+    // with the methods of PackedDna, rather than those of SuffixArray over raw bytes.
+    pub fn build_packed(&self, dna: &PackedDna) -> Box<dyn SuffixArray> {
+        if dna.is_empty() {
+            return Box::new(DnaSuffixArray { sa: Vec::new() });
+        }
+        let sa = SaIsBuilder::new().build_text(dna, 5);
+        Box::new(DnaSuffixArray { sa })
+    }
+
+    // Reads a FASTA or FASTQ file, and returns its packed sequences with their suffix array.
+    pub fn build_fasta(&self, fasta: &[u8]) -> std::io::Result<(PackedDna, Box<dyn SuffixArray>)> {
+        let dna = PackedDna::from_fasta(fasta)?;
+        let suffix_array = self.build_packed(&dna);
+        Ok((dna, suffix_array))
+    }
+}
+
+impl Default for DnaBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct DnaSuffixArray {
+    sa: Vec<TextSize>,
+}
+
+impl SuffixArray for DnaSuffixArray {
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = TextSize> + 'a> {
+        Box::new(self.sa.iter().copied())
+    }
+
+    fn array(&self) -> &[TextSize] {
+        &self.sa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    #[test]
+    fn packing() {
+        let dna = PackedDna::new(b"ACGTNNacgtRNNA");
+        assert_eq!(dna.len(), 14);
+        assert_eq!(dna.to_bytes(), b"ACGTNNACGTNNNA");
+        assert_eq!(dna.exceptions, [4..6, 10..13]);
+
+        let fasta = b">chr1 test\nACGT\nNNAC\r\n;comment\n>chr2\nGGTT\n";
+        let dna = PackedDna::from_fasta(fasta).unwrap();
+        assert_eq!(dna.to_bytes(), b"ACGTNNACGGTT");
+        assert_eq!(dna.records(), 2);
+
+        // FASTQ quality lines are not bases.
+        let dna = PackedDna::from_fasta(b"@r1\nACGT\n+\nIIII\n@r2\nGG\n+\nII").unwrap();
+        assert_eq!(dna.to_bytes(), b"ACGTGG");
+        assert!(PackedDna::from_fasta(b"@r1\nACGT\n+\nII").is_err());
+
+        let report = PackedDna::new(&[b'A'; 1000]).memory_report();
+        assert_eq!(report.packed_bytes, 32 * 8);
+        assert_eq!(report.suffix_array_bytes, 4000);
+    }
+
+    #[test]
+    fn both_strands() {
+        assert_eq!(reverse_complement(b"AACGTn"), b"nACGTT");
+        assert_eq!(reverse_complement(b"gatRYc"), b"gRYatc");
+
+        let text = b"TTGCAAGGCATGCCTTACGT";
+        let sa = SaIsBuilder::new().build(text);
+        let hit = |pos, strand| StrandHit { pos, strand };
+        // The reverse complement of GCA is TGC.
+        assert_eq!(
+            locate_both_strands(text, sa.array(), b"GCA"),
+            [
+                hit(1, Strand::Reverse),
+                hit(2, Strand::Forward),
+                hit(7, Strand::Forward),
+                hit(10, Strand::Reverse)
+            ]
+        );
+        // ACGT is its own reverse complement.
+        assert_eq!(
+            locate_both_strands(text, sa.array(), b"ACGT"),
+            [hit(16, Strand::Forward), hit(16, Strand::Reverse)]
+        );
+        assert!(locate_both_strands(text, sa.array(), b"GGG").is_empty());
+
+        // Over a packed text, soft-masked bases match either case, and IUPAC codes are N.
+        let dna = PackedDna::new(b"acgtACGTRYacgNTTTT");
+        let sa = DnaBuilder::new().build_packed(&dna);
+        // The reverse complement of CGT is ACG.
+        assert_eq!(
+            dna.locate_both_strands(sa.array(), b"CGT"),
+            [
+                hit(0, Strand::Reverse),
+                hit(1, Strand::Forward),
+                hit(4, Strand::Reverse),
+                hit(5, Strand::Forward),
+                hit(10, Strand::Reverse)
+            ]
+        );
+        // The reverse complement of gtr is yac, which is packed as NAC.
+        assert_eq!(
+            dna.locate_both_strands(sa.array(), b"gtr"),
+            [hit(6, Strand::Forward), hit(9, Strand::Reverse)]
+        );
+
+        // No hits across records.
+        let (dna, sa) = DnaBuilder::new().build_fasta(b">a\nAC\n>b\nGTT").unwrap();
+        assert!(dna.locate_both_strands(sa.array(), b"cg").is_empty());
+        assert_eq!(
+            dna.locate_both_strands(sa.array(), b"gt"),
+            [hit(0, Strand::Reverse), hit(2, Strand::Forward)]
+        );
+    }
+
+    #[test]
+    fn packed_search() {
+        let dna = PackedDna::new(b"TTGCAAGGCATGCCTTACGTNNAC");
+        let sa = DnaBuilder::new().build_packed(&dna);
+        assert_eq!(dna.locate(sa.array(), b"GCA"), [2, 7]);
+        assert_eq!(dna.locate(sa.array(), b"gca"), [2, 7]);
+        assert_eq!(dna.find_range(sa.array(), b"").len(), dna.len());
+        // Any byte other than ACGT matches N.
+        assert_eq!(dna.locate(sa.array(), b"TNX"), [19]);
+        assert_eq!(dna.locate(sa.array(), b"AC"), [16, 22]);
+        assert!(dna.locate(sa.array(), b"ACA").is_empty());
+
+        // Soft-masked bases match either case.
+        let dna = PackedDna::new(b"acgtACGTacgtTTTT");
+        let sa = DnaBuilder::new().build_packed(&dna);
+        assert_eq!(dna.locate(sa.array(), b"ACG"), [0, 4, 8]);
+        assert_eq!(dna.locate(sa.array(), b"acg"), [0, 4, 8]);
+
+        let mut rand: usize = 29;
+        let text: Vec<u8> = (0..500)
+            .map(|_| b"ACGTN"[testing::next_random(&mut rand) % 5])
+            .collect();
+        let dna = PackedDna::new(&text);
+        let sa = DnaBuilder::new().build_packed(&dna);
+        for len in 0..6 {
+            let start = testing::next_random(&mut rand) % (text.len() - len);
+            let pattern = &text[start..start + len];
+            assert_eq!(
+                dna.locate(sa.array(), pattern),
+                locate(&text, sa.array(), pattern)
+            );
+        }
+    }
+
+    #[test]
+    fn records() {
+        let (dna, sa) = DnaBuilder::new().build_fasta(b">a\nAC\n>b\nGT").unwrap();
+        assert_eq!(dna.to_bytes(), b"ACGT");
+        // CG spans the two records.
+        assert!(dna.locate(sa.array(), b"CG").is_empty());
+        assert_eq!(dna.locate(sa.array(), b"G"), [2]);
+        assert_eq!(dna.find_range(sa.array(), b"CG").len(), 1);
+
+        // Header-only and empty inputs.
+        for fasta in [&b">empty\n"[..], b""] {
+            let (dna, sa) = DnaBuilder::new().build_fasta(fasta).unwrap();
+            assert!(dna.is_empty());
+            assert_eq!(dna.records(), 0);
+            assert!(sa.array().is_empty());
+            assert!(dna.locate(sa.array(), b"A").is_empty());
+        }
+        let (dna, _) = DnaBuilder::new()
+            .build_fasta(b">a\n>b\nAC\n>c\n\n>d\nG")
+            .unwrap();
+        assert_eq!(dna.records(), 2);
+    }
+
+    #[test]
+    fn matches_byte_suffix_array() {
+        let mut rand: usize = 19;
+        for n in 1..40 {
+            let text: Vec<u8> = (0..n * 13)
+                .map(|_| {
+                    let r = testing::next_random(&mut rand);
+                    // Mostly bases, with a few N runs.
+                    if r % 50 < 2 + n % 3 {
+                        b'N'
+                    } else {
+                        b"ACGT"[r % (2 + n % 3)]
+                    }
+                })
+                .collect();
+            let expected = SaIsBuilder::new().build(&text);
+            let sa = DnaBuilder::new().build_packed(&PackedDna::new(&text));
+            assert_eq!(sa.array(), expected.array());
+        }
+    }
+}
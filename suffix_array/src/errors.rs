@@ -0,0 +1,8 @@
+// Errors shared by the readers of serialized and input data.
+
+use std::io::{Error, ErrorKind};
+
+// An error for malformed input data.
+pub(crate) fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
@@ -0,0 +1,251 @@
+// Reading FASTA and FASTQ sequences, and indexing them together.
+//
+// FASTA records start with a '>' header line, followed by sequence lines. Lines starting with ';'
+// are comments. FASTQ records have an '@' header line, sequence lines, a '+' line, and quality
+// lines of the same total length as the sequence. The name of a record is the first word of its
+// header.
+//
+// A SequenceIndex builds a generalized suffix array over all the records: the sequences are
+// concatenated, each followed by a '\n', and hits are mapped back to (record name, offset).
+
+use super::dna::{reverse_complement, Strand};
+use super::errors::invalid_data;
+use super::records::{locate_within_records, RecordIndex};
+use super::sa_is::SaIsBuilder;
+use super::suffix_array::{SuffixArray, SuffixArrayBuilder};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SequenceRecord {
+    pub name: String,
+    pub sequence: Vec<u8>,
+}
+
+fn record_name(header: &[u8]) -> String {
+    let name = header.split(|ch| ch.is_ascii_whitespace()).next().unwrap();
+    String::from_utf8_lossy(name).to_string()
+}
+
+// Reads the records of a FASTA or FASTQ file, depending on its first char.
+pub fn read_sequences(data: &[u8]) -> std::io::Result<Vec<SequenceRecord>> {
+    let mut lines = data
+        .split(|&ch| ch == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .peekable();
+    let mut records: Vec<SequenceRecord> = Vec::new();
+    let fastq = data.first() == Some(&b'@');
+
+    while let Some(line) = lines.next() {
+        if line.starts_with(b";") {
+            continue;
+        }
+        let header = match (fastq, line) {
+            (false, [b'>', header @ ..]) | (true, [b'@', header @ ..]) => header,
+            _ => return Err(invalid_data("missing record header")),
+        };
+        let mut record = SequenceRecord {
+            name: record_name(header),
+            sequence: Vec::new(),
+        };
+
+        // Sequence lines, up to the next header, or the '+' line of FASTQ.
+        let end = if fastq { b'+' } else { b'>' };
+        while let Some(line) = lines.next_if(|line| line[0] != end) {
+            if !line.starts_with(b";") || fastq {
+                record.sequence.extend_from_slice(line);
+            }
+        }
+
+        if fastq {
+            if lines.next().is_none() {
+                return Err(invalid_data("missing FASTQ '+' line"));
+            }
+            let mut quality_len = 0;
+            while quality_len < record.sequence.len() {
+                let line = lines
+                    .next()
+                    .ok_or_else(|| invalid_data("truncated FASTQ quality"))?;
+                quality_len += line.len();
+            }
+            if quality_len != record.sequence.len() {
+                return Err(invalid_data("FASTQ quality length mismatch"));
+            }
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+// A generalized suffix array over sequence records.
+pub struct SequenceIndex {
+    names: Vec<String>,
+
+    // The uppercase sequences, each followed by a '\n'.
+    text: Vec<u8>,
+    records: RecordIndex,
+    suffix_array: Box<dyn SuffixArray>,
+}
+
+impl SequenceIndex {
+    // The sequences are indexed in uppercase, with a suffix array in byte order, since the
+    // searches compare raw bytes. There must be at least one record.
+    pub fn new(records: Vec<SequenceRecord>) -> SequenceIndex {
+        assert!(!records.is_empty());
+        let mut names: Vec<String> = Vec::with_capacity(records.len());
+        let mut text: Vec<u8> = Vec::new();
+        for record in records {
+            names.push(record.name);
+            text.extend(record.sequence.iter().map(|ch| ch.to_ascii_uppercase()));
+            text.push(b'\n');
+        }
+        let suffix_array = SaIsBuilder::new().build(&text);
+        SequenceIndex {
+            names,
+            records: RecordIndex::lines(&text),
+            text,
+            suffix_array,
+        }
+    }
+
+    // Number of records.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, record: usize) -> &str {
+        &self.names[record]
+    }
+
+    pub fn sequence(&self, record: usize) -> &[u8] {
+        let range = self.records.range(record);
+        &self.text[range.start..range.end - 1]
+    }
+
+    // Total length of the sequences.
+    pub fn total_len(&self) -> usize {
+        self.text.len() - self.names.len()
+    }
+
+    // Returns the (record name, offset) of each occurrence of the pattern, ignoring case, in
+    // record order.
+    pub fn locate(&self, pattern: &[u8]) -> Vec<(&str, usize)> {
+        let pattern = pattern.to_ascii_uppercase();
+        locate_within_records(
+            &self.text,
+            self.suffix_array.array(),
+            &pattern,
+            &self.records,
+        )
+        .into_iter()
+        .map(|p| (self.names[p.record].as_str(), p.offset))
+        .collect()
+    }
+
+    // Returns the (record name, offset, strand) of each occurrence of the pattern on either
+    // strand, in record order. Offsets are on the forward strand: a reverse strand hit means the
+    // reverse complement of the pattern occurs at the offset.
+    pub fn locate_both_strands(&self, pattern: &[u8]) -> Vec<(&str, usize, Strand)> {
+        let sa = self.suffix_array.array();
+        let hits = |pattern: &[u8], strand: Strand| {
+            let pattern = pattern.to_ascii_uppercase();
+            locate_within_records(&self.text, sa, &pattern, &self.records)
+                .into_iter()
+                .map(move |p| (p.record, p.offset, strand))
+        };
+        let mut hits_both: Vec<(usize, usize, Strand)> = hits(pattern, Strand::Forward)
+            .chain(hits(&reverse_complement(pattern), Strand::Reverse))
+            .collect();
+        hits_both.sort_unstable();
+        hits_both
+            .into_iter()
+            .map(|(record, offset, strand)| (self.names[record].as_str(), offset, strand))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fasta() {
+        let fasta = b">chr1 first\nACGTAC\r\nGTNN\n;comment\n\n>chr2\nacgg\n>empty\n>chr3\nTTAC\n";
+        let records = read_sequences(fasta).unwrap();
+        let names: Vec<&str> = records.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["chr1", "chr2", "empty", "chr3"]);
+        assert_eq!(records[0].sequence, b"ACGTACGTNN");
+        assert_eq!(records[1].sequence, b"acgg");
+        assert!(records[2].sequence.is_empty());
+
+        let index = SequenceIndex::new(records);
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.total_len(), 18);
+        assert_eq!(index.sequence(3), b"TTAC");
+        assert_eq!(
+            index.locate(b"acg"),
+            [("chr1", 0), ("chr1", 4), ("chr2", 0)]
+        );
+        assert_eq!(
+            index.locate(b"AC"),
+            [("chr1", 0), ("chr1", 4), ("chr2", 0), ("chr3", 2)]
+        );
+        // No hits across records.
+        assert!(index.locate(b"NNACGG").is_empty());
+        assert!(index.locate(b"GGTT").is_empty());
+
+        // The reverse complement of CGT is ACG.
+        assert_eq!(
+            index.locate_both_strands(b"cgt"),
+            [
+                ("chr1", 0, Strand::Reverse),
+                ("chr1", 1, Strand::Forward),
+                ("chr1", 4, Strand::Reverse),
+                ("chr1", 5, Strand::Forward),
+                ("chr2", 0, Strand::Reverse)
+            ]
+        );
+        assert_eq!(
+            index.locate_both_strands(b"GTAA"),
+            [("chr3", 0, Strand::Reverse)]
+        );
+
+        assert!(read_sequences(b"ACGT\n").is_err());
+
+        let index = SequenceIndex::new(read_sequences(b">a\nACGT\n>b\nTTGCA").unwrap());
+        assert_eq!(index.locate(b"ACGT"), [("a", 0)]);
+        assert_eq!(index.locate(b"GCA"), [("b", 2)]);
+    }
+
+    #[test]
+    fn fastq() {
+        let fastq = b"@read1 x\nACGT\n+\n!!!!\n@read2\nGG\nTT\n+read2\n!!\n!!\n@read3\n\n+\n\n";
+        let records = read_sequences(fastq).unwrap();
+        assert_eq!(
+            records,
+            [
+                SequenceRecord {
+                    name: "read1".to_string(),
+                    sequence: b"ACGT".to_vec()
+                },
+                SequenceRecord {
+                    name: "read2".to_string(),
+                    sequence: b"GGTT".to_vec()
+                },
+                SequenceRecord {
+                    name: "read3".to_string(),
+                    sequence: Vec::new()
+                },
+            ]
+        );
+        // '@' is a valid quality char.
+        let records = read_sequences(b"@r\nAC\n+\n@@\n@s\nG\n+\nI\n").unwrap();
+        assert_eq!(records.len(), 2);
+
+        assert!(read_sequences(b"@r\nACGT\n+\n!!\n").is_err());
+        assert!(read_sequences(b"@r\nACGT\n").is_err());
+    }
+}
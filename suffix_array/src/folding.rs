@@ -0,0 +1,210 @@
+// Case-insensitive indexing over a folded view of a text.
+//
+// The suffix array is built over the folded text, and patterns are folded the same way before
+// searching. A position map takes the folded positions back to the original bytes, for
+// reporting and context extraction.
+//
+// ASCII folding lowercases A-Z, and keeps every other byte, so positions do not change.
+// Unicode folding lowercases the chars of the UTF-8 parts of the text, which approximates Unicode
+// simple case folding. It may change the length of a char, e.g. the Kelvin sign 'K' (3 bytes)
+// folds to 'k' (1 byte). Invalid UTF-8 bytes are kept as they are.
+
+use std::ops::Range;
+
+use super::naive::NaiveBuilder;
+use super::suffix_array::{SuffixArray, SuffixArrayBuilder, TextSize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CaseFolding {
+    Ascii,
+    Unicode,
+}
+
+// Folds a char to a single char.
+fn fold_char(ch: char) -> char {
+    // Simple case folding differs from lowercasing for these chars.
+    match ch {
+        'ς' => return 'σ',
+        'ſ' => return 's',
+        'µ' => return 'μ',
+        'ϐ' => return 'β',
+        'ϑ' => return 'θ',
+        'ϕ' => return 'φ',
+        'ϖ' => return 'π',
+        'ϰ' => return 'κ',
+        'ϱ' => return 'ρ',
+        'ϵ' => return 'ε',
+        _ => {}
+    }
+    // Keep the chars that lowercase to more than one char, e.g. 'İ'.
+    let mut lower = ch.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(folded), None) => folded,
+        _ => ch,
+    }
+}
+
+// A folded text, and the map back to the original text.
+pub struct FoldedText {
+    folded: Vec<u8>,
+
+    // Original position of each folded byte, or None if the positions are the same.
+    original: Option<Vec<TextSize>>,
+    original_len: usize,
+}
+
+impl FoldedText {
+    pub fn new(text: &[u8], folding: CaseFolding) -> FoldedText {
+        if folding == CaseFolding::Ascii {
+            return FoldedText {
+                folded: text.to_ascii_lowercase(),
+                original: None,
+                original_len: text.len(),
+            };
+        }
+
+        let mut folded: Vec<u8> = Vec::with_capacity(text.len());
+        let mut original: Vec<TextSize> = Vec::with_capacity(text.len());
+        let mut pos = 0;
+        for chunk in text.utf8_chunks() {
+            for ch in chunk.valid().chars() {
+                let mut buffer = [0; 4];
+                let encoded = fold_char(ch).encode_utf8(&mut buffer);
+                folded.extend_from_slice(encoded.as_bytes());
+                original.resize(folded.len(), pos as TextSize);
+                pos += ch.len_utf8();
+            }
+            for &byte in chunk.invalid() {
+                folded.push(byte);
+                original.push(pos as TextSize);
+                pos += 1;
+            }
+        }
+        FoldedText {
+            folded,
+            original: Some(original),
+            original_len: text.len(),
+        }
+    }
+
+    // The folded text.
+    pub fn text(&self) -> &[u8] {
+        &self.folded
+    }
+
+    // Returns the original position of a folded position, which may be the end of the text.
+    pub fn original_pos(&self, pos: usize) -> usize {
+        match &self.original {
+            _ if pos == self.folded.len() => self.original_len,
+            Some(original) => original[pos] as usize,
+            None => pos,
+        }
+    }
+
+    // Returns the original range of a range of folded chars.
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        self.original_pos(range.start)..self.original_pos(range.end)
+    }
+}
+
+// A suffix array over a folded text.
+pub struct FoldedIndex {
+    folding: CaseFolding,
+    text: FoldedText,
+    suffix_array: Box<dyn SuffixArray>,
+}
+
+impl FoldedIndex {
+    pub fn new(
+        sa_builder: &dyn SuffixArrayBuilder,
+        text: &[u8],
+        folding: CaseFolding,
+    ) -> FoldedIndex {
+        let text = FoldedText::new(text, folding);
+        // Some builders, such as SA-IS, need a non-empty text.
+        let suffix_array = if text.text().is_empty() {
+            NaiveBuilder::new().build(text.text())
+        } else {
+            sa_builder.build(text.text())
+        };
+        FoldedIndex {
+            folding,
+            text,
+            suffix_array,
+        }
+    }
+
+    pub fn folded_text(&self) -> &FoldedText {
+        &self.text
+    }
+
+    // The suffix array of the folded text.
+    pub fn suffix_array(&self) -> &dyn SuffixArray {
+        self.suffix_array.as_ref()
+    }
+
+    // Returns the original byte ranges of the occurrences of the pattern, ignoring case, in text
+    // order.
+    pub fn locate(&self, pattern: &[u8]) -> Vec<Range<usize>> {
+        let pattern = FoldedText::new(pattern, self.folding);
+        let pattern = pattern.text();
+        self.suffix_array
+            .locate(self.text.text(), pattern)
+            .into_iter()
+            .map(|pos| pos as usize)
+            .map(|pos| self.text.original_range(pos..pos + pattern.len()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn ascii() {
+        let text = b"Hello HELLO hello h\xe9llo";
+        let index = FoldedIndex::new(&SaIsBuilder::new(), text, CaseFolding::Ascii);
+        assert_eq!(index.folded_text().text(), b"hello hello hello h\xe9llo");
+        assert_eq!(index.locate(b"hELLo"), [0..5, 6..11, 12..17]);
+        assert_eq!(index.locate(b"H\xe9LLO"), [18..23]);
+        assert!(index.locate(b"x").is_empty());
+    }
+
+    #[test]
+    fn unicode() {
+        let text = "Ὀδυσσεύς ὈΔΥΣΣΕΎΣ, 5 \u{212A}M Straße STRAẞE".as_bytes();
+        let index = FoldedIndex::new(&SaIsBuilder::new(), text, CaseFolding::Unicode);
+        let found = |pattern: &str| -> Vec<&str> {
+            index
+                .locate(pattern.as_bytes())
+                .into_iter()
+                .map(|r| std::str::from_utf8(&text[r]).unwrap())
+                .collect()
+        };
+        assert_eq!(found("ὀδυσσεύσ"), ["Ὀδυσσεύς", "ὈΔΥΣΣΕΎΣ"]);
+        assert_eq!(found("ΟΔΥΣ"), Vec::<&str>::new());
+        assert_eq!(found("5 km"), ["5 \u{212A}M"]);
+        assert_eq!(found("STRASSE"), Vec::<&str>::new());
+        assert_eq!(found("straße"), ["Straße", "STRAẞE"]);
+
+        // Invalid UTF-8 is kept, and the positions still map back.
+        let text = b"\xffAB\xc3\xc3\x89\xff";
+        let folded = FoldedText::new(text, CaseFolding::Unicode);
+        assert_eq!(folded.text(), b"\xffab\xc3\xc3\xa9\xff");
+        assert_eq!(folded.original_range(3..6), 3..6);
+        assert_eq!(folded.original_pos(7), 7);
+    }
+
+    #[test]
+    fn empty() {
+        for folding in [CaseFolding::Ascii, CaseFolding::Unicode] {
+            let index = FoldedIndex::new(&SaIsBuilder::new(), b"", folding);
+            assert!(index.suffix_array().array().is_empty());
+            assert!(index.locate(b"a").is_empty());
+            assert!(index.locate(b"").is_empty());
+        }
+    }
+}
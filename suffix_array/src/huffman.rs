@@ -0,0 +1,322 @@
+// Canonical Huffman coding, used by the block compressor.
+
+// Longest code length allowed. Code lengths are stored in a byte each.
+pub const MAX_CODE_LENGTH: u8 = 20;
+
+// Writes bits, most significant bit first.
+pub struct BitWriter {
+    bytes: Vec<u8>,
+
+    // Pending bits, not yet written to bytes.
+    buffer: u64,
+
+    // Number of pending bits in buffer.
+    num_bits: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> BitWriter {
+        BitWriter {
+            bytes: Vec::new(),
+            buffer: 0,
+            num_bits: 0,
+        }
+    }
+
+    // Writes the lowest (len) bits of value.
+    pub fn write(&mut self, value: u32, len: u8) {
+        debug_assert!(len <= 32);
+        self.buffer = (self.buffer << len) | (value as u64 & ((1u64 << len) - 1));
+        self.num_bits += len as u32;
+        while self.num_bits >= 8 {
+            self.num_bits -= 8;
+            self.bytes.push((self.buffer >> self.num_bits) as u8);
+        }
+    }
+
+    // Flushes the pending bits, padded with zeros, and returns the bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.num_bits > 0 {
+            self.bytes.push((self.buffer << (8 - self.num_bits)) as u8);
+        }
+        self.bytes
+    }
+}
+
+// Reads bits written by BitWriter.
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+
+    // Position of the next bit.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        BitReader { bytes, pos: 0 }
+    }
+
+    // Reads a single bit, or None at the end of the input.
+    pub fn read_bit(&mut self) -> Option<u32> {
+        let byte = *self.bytes.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1;
+        self.pos += 1;
+        Some(bit as u32)
+    }
+}
+
+// Computes code lengths for the given symbol frequencies. Unused symbols get length 0.
+pub fn code_lengths(freqs: &[u32]) -> Vec<u8> {
+    let mut freqs: Vec<u64> = freqs.iter().map(|&f| f as u64).collect();
+    loop {
+        let lengths = unlimited_code_lengths(&freqs);
+        if lengths.iter().all(|&l| l <= MAX_CODE_LENGTH) {
+            return lengths;
+        }
+
+        // Flatten the distribution and retry, as bzip2 does.
+        for f in freqs.iter_mut() {
+            if *f > 0 {
+                *f = *f / 2 + 1;
+            }
+        }
+    }
+}
+
+// Computes Huffman code lengths without a length limit.
+fn unlimited_code_lengths(freqs: &[u64]) -> Vec<u8> {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    let mut lengths: Vec<u8> = vec![0; freqs.len()];
+
+    // Nodes [0..freqs.len()] are leaves, the rest are internal nodes.
+    let mut parent: Vec<usize> = vec![usize::MAX; freqs.len()];
+    let mut heap = BinaryHeap::new();
+    for (symbol, &f) in freqs.iter().enumerate() {
+        if f > 0 {
+            heap.push(Reverse((f, symbol)));
+        }
+    }
+
+    // A single symbol still needs a 1 bit code.
+    if heap.len() == 1 {
+        let Reverse((_, symbol)) = heap.pop().unwrap();
+        lengths[symbol] = 1;
+        return lengths;
+    }
+
+    while heap.len() >= 2 {
+        let Reverse((f1, n1)) = heap.pop().unwrap();
+        let Reverse((f2, n2)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[n1] = node;
+        parent[n2] = node;
+        heap.push(Reverse((f1 + f2, node)));
+    }
+
+    // The depth of each leaf is its code length.
+    for symbol in 0..freqs.len() {
+        if freqs[symbol] == 0 {
+            continue;
+        }
+        let mut depth = 0;
+        let mut node = symbol;
+        while parent[node] != usize::MAX {
+            node = parent[node];
+            depth += 1;
+        }
+        lengths[symbol] = depth;
+    }
+    lengths
+}
+
+// Assigns canonical codes: shorter codes first, then by symbol value.
+fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut length_count: Vec<u32> = vec![0; MAX_CODE_LENGTH as usize + 1];
+    for &l in lengths.iter() {
+        length_count[l as usize] += 1;
+    }
+    length_count[0] = 0;
+
+    let mut next_code: Vec<u32> = vec![0; MAX_CODE_LENGTH as usize + 1];
+    let mut code = 0;
+    for len in 1..=MAX_CODE_LENGTH as usize {
+        code = (code + length_count[len - 1]) << 1;
+        next_code[len] = code;
+    }
+
+    let mut codes: Vec<u32> = vec![0; lengths.len()];
+    for (symbol, &l) in lengths.iter().enumerate() {
+        if l > 0 {
+            codes[symbol] = next_code[l as usize];
+            next_code[l as usize] += 1;
+        }
+    }
+    codes
+}
+
+// Encodes symbols with a canonical Huffman code.
+pub struct Encoder {
+    lengths: Vec<u8>,
+    codes: Vec<u32>,
+}
+
+impl Encoder {
+    pub fn new(lengths: &[u8]) -> Encoder {
+        Encoder {
+            lengths: lengths.to_vec(),
+            codes: canonical_codes(lengths),
+        }
+    }
+
+    pub fn write(&self, writer: &mut BitWriter, symbol: usize) {
+        debug_assert!(self.lengths[symbol] > 0);
+        writer.write(self.codes[symbol], self.lengths[symbol]);
+    }
+}
+
+// Decodes symbols with a canonical Huffman code.
+pub struct Decoder {
+    // Number of codes of each length.
+    length_count: Vec<u32>,
+
+    // Symbols sorted by (code length, symbol).
+    symbols: Vec<usize>,
+}
+
+impl Decoder {
+    pub fn new(lengths: &[u8]) -> Decoder {
+        let mut length_count: Vec<u32> = vec![0; MAX_CODE_LENGTH as usize + 1];
+        let mut symbols: Vec<usize> = Vec::new();
+        for len in 1..=MAX_CODE_LENGTH {
+            for (symbol, &l) in lengths.iter().enumerate() {
+                if l == len {
+                    length_count[len as usize] += 1;
+                    symbols.push(symbol);
+                }
+            }
+        }
+        Decoder {
+            length_count,
+            symbols,
+        }
+    }
+
+    // Reads one symbol, or None if the input is exhausted or invalid.
+    pub fn read(&self, reader: &mut BitReader) -> Option<usize> {
+        // (code - first) is the index of the code among codes of the same length.
+        let mut code: u32 = 0;
+        let mut first: u32 = 0;
+        let mut index: u32 = 0;
+        for len in 1..=MAX_CODE_LENGTH as usize {
+            code |= reader.read_bit()?;
+            let count = self.length_count[len];
+            if code < first + count {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sum of 2^-length over the used symbols, scaled by 2^MAX_CODE_LENGTH. A complete prefix code
+    // sums to exactly 1.
+    fn kraft_sum(lengths: &[u8]) -> u64 {
+        lengths
+            .iter()
+            .filter(|&&l| l > 0)
+            .map(|&l| 1u64 << (MAX_CODE_LENGTH - l))
+            .sum()
+    }
+
+    #[test]
+    fn code_lengths_limit() {
+        // Fibonacci frequencies give the deepest Huffman trees.
+        let mut freqs: Vec<u32> = vec![1, 1];
+        while freqs.len() < 40 {
+            freqs.push(freqs[freqs.len() - 1] + freqs[freqs.len() - 2]);
+        }
+        assert!(
+            unlimited_code_lengths(&freqs.iter().map(|&f| f as u64).collect::<Vec<u64>>())
+                .iter()
+                .any(|&l| l > MAX_CODE_LENGTH)
+        );
+        let lengths = code_lengths(&freqs);
+        assert!(lengths.iter().all(|&l| (1..=MAX_CODE_LENGTH).contains(&l)));
+        assert_eq!(kraft_sum(&lengths), 1 << MAX_CODE_LENGTH);
+
+        assert_eq!(code_lengths(&[3, 1, 1, 0]), [1, 2, 2, 0]);
+    }
+
+    #[test]
+    fn code_lengths_edge_cases() {
+        assert!(code_lengths(&[]).is_empty());
+        assert_eq!(code_lengths(&[0, 0, 0]), [0, 0, 0]);
+        // A single symbol still gets a 1 bit code.
+        assert_eq!(code_lengths(&[0, 7, 0]), [0, 1, 0]);
+    }
+
+    #[test]
+    fn canonical() {
+        // Shorter codes first, then by symbol.
+        let lengths = [2, 1, 3, 0, 3];
+        assert_eq!(canonical_codes(&lengths), [0b10, 0b0, 0b110, 0, 0b111]);
+
+        let encoder = Encoder::new(&lengths);
+        let decoder = Decoder::new(&lengths);
+        let symbols = [0, 1, 2, 4, 4, 1, 0];
+        let mut writer = BitWriter::new();
+        for &symbol in &symbols {
+            encoder.write(&mut writer, symbol);
+        }
+        let bytes = writer.finish();
+        assert_eq!(bytes, [0b10011011, 0b11110100]);
+        let mut reader = BitReader::new(&bytes);
+        for &symbol in &symbols {
+            assert_eq!(decoder.read(&mut reader), Some(symbol));
+        }
+    }
+
+    #[test]
+    fn bits_round_trip() {
+        let values: [(u32, u8); 7] = [
+            (1, 1),
+            (0, 3),
+            (0x5a, 7),
+            (u32::MAX, 32),
+            (0, 0),
+            (0x12345, 17),
+            (3, 2),
+        ];
+        let mut writer = BitWriter::new();
+        for &(value, len) in &values {
+            writer.write(value, len);
+        }
+        let bytes = writer.finish();
+        assert_eq!(bytes.len(), 62usize.div_ceil(8));
+
+        let mut reader = BitReader::new(&bytes);
+        for &(value, len) in &values {
+            let mut read = 0u64;
+            for _ in 0..len {
+                read = read << 1 | reader.read_bit().unwrap() as u64;
+            }
+            assert_eq!(read, value as u64 & ((1u64 << len) - 1));
+        }
+        // The padding, then the end of the input.
+        for _ in 62..64 {
+            assert_eq!(reader.read_bit(), Some(0));
+        }
+        assert_eq!(reader.read_bit(), None);
+        assert_eq!(BitWriter::new().finish(), []);
+    }
+}
@@ -0,0 +1,192 @@
+// Longest common prefix (LCP) computations over a suffix array.
+
+use super::suffix_array::{inverse_suffix_array, TextSize};
+
+// Computes the LCP array with Kasai's algorithm.
+// lcp[i] = length of the longest common prefix of suffixes sa[i - 1] and sa[i], and lcp[0] = 0.
+// e.g. the LCP array of "banana" (sa = [5, 3, 1, 0, 4, 2]) is [0, 1, 3, 0, 0, 2].
+pub fn lcp_array(text: &[u8], sa: &[TextSize]) -> Vec<TextSize> {
+    let inverse_sa = inverse_suffix_array(sa);
+    lcp_array_with_inverse(text, sa, &inverse_sa)
+}
+
+// Same as lcp_array(), but reuses an already computed inverse suffix array.
+pub fn lcp_array_with_inverse(
+    text: &[u8],
+    sa: &[TextSize],
+    inverse_sa: &[TextSize],
+) -> Vec<TextSize> {
+    let len = text.len();
+    let mut lcp: Vec<TextSize> = vec![0; len];
+
+    // Visit the suffixes in text order. The LCP drops by at most 1 from one suffix to the next.
+    let mut h: usize = 0;
+    for pos in 0..len {
+        let rank = inverse_sa[pos] as usize;
+        if rank == 0 {
+            h = 0;
+            continue;
+        }
+        let prev_pos = sa[rank - 1] as usize;
+        while pos + h < len && prev_pos + h < len && text[pos + h] == text[prev_pos + h] {
+            h += 1;
+        }
+        lcp[rank] = h as TextSize;
+        h = h.saturating_sub(1);
+    }
+    lcp
+}
+
+// An interval of suffix array rows [start, end) whose suffixes share a common prefix of length
+// lcp, and which is maximal: it corresponds to an internal node of the suffix tree.
+// e.g. for "banana", the suffixes "ana", "anana" at rows [1, 3) share the prefix "ana".
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LcpInterval {
+    pub lcp: TextSize,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Enumerates all the LCP intervals, including the root interval [0, len) with lcp 0.
+// Child intervals are returned before their parents.
+pub fn lcp_intervals(lcp: &[TextSize]) -> Vec<LcpInterval> {
+    let len = lcp.len();
+    let mut intervals: Vec<LcpInterval> = Vec::new();
+    if len == 0 {
+        return intervals;
+    }
+
+    // A stack of open intervals, with increasing lcp values.
+    let mut stack: Vec<LcpInterval> = vec![LcpInterval {
+        lcp: 0,
+        start: 0,
+        end: len,
+    }];
+    // A final 0 closes all the open intervals.
+    let values = lcp[1..].iter().copied().chain(std::iter::once(0));
+    for (i, value) in (1..=len).zip(values) {
+        let mut start = i - 1;
+        while value < stack.last().unwrap().lcp {
+            let mut interval = stack.pop().unwrap();
+            interval.end = i;
+            start = interval.start;
+            intervals.push(interval);
+        }
+        if value > stack.last().unwrap().lcp {
+            stack.push(LcpInterval {
+                lcp: value,
+                start,
+                end: len,
+            });
+        }
+    }
+    intervals.push(stack.pop().unwrap());
+    intervals
+}
+
+// Answers range minimum queries in O(1), using a sparse table of O(n log n) size.
+pub struct RangeMin {
+    // levels[k][i] = min(values[i..i + 2^k]).
+    levels: Vec<Vec<TextSize>>,
+}
+
+impl RangeMin {
+    pub fn new(values: &[TextSize]) -> RangeMin {
+        let mut levels: Vec<Vec<TextSize>> = vec![values.to_vec()];
+        let mut width = 1;
+        while width * 2 <= values.len() {
+            let prev = levels.last().unwrap();
+            let level: Vec<TextSize> = (0..prev.len() - width)
+                .map(|i| prev[i].min(prev[i + width]))
+                .collect();
+            levels.push(level);
+            width *= 2;
+        }
+        RangeMin { levels }
+    }
+
+    // Returns min(values[start..end]). The range must not be empty.
+    pub fn min(&self, start: usize, end: usize) -> TextSize {
+        assert!(start < end);
+        let k = (usize::BITS - 1 - (end - start).leading_zeros()) as usize;
+        let level = &self.levels[k];
+        level[start].min(level[end - (1 << k)])
+    }
+}
+
+// Answers longest common extension (LCE) queries: the length of the longest common prefix of
+// any two suffixes of the text.
+pub struct LceIndex {
+    inverse_sa: Vec<TextSize>,
+    lcp_min: RangeMin,
+}
+
+impl LceIndex {
+    pub fn new(text: &[u8], sa: &[TextSize]) -> LceIndex {
+        let inverse_sa = inverse_suffix_array(sa);
+        let lcp = lcp_array_with_inverse(text, sa, &inverse_sa);
+        LceIndex {
+            inverse_sa,
+            lcp_min: RangeMin::new(&lcp),
+        }
+    }
+
+    // Returns the length of the longest common prefix of the suffixes at pos a and b.
+    pub fn lce(&self, a: TextSize, b: TextSize) -> TextSize {
+        let len = self.inverse_sa.len() as TextSize;
+        if a == b {
+            return len - a;
+        }
+        if a == len || b == len {
+            return 0;
+        }
+        let rank_a = self.inverse_sa[a as usize] as usize;
+        let rank_b = self.inverse_sa[b as usize] as usize;
+        self.lcp_min
+            .min(rank_a.min(rank_b) + 1, rank_a.max(rank_b) + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    #[test]
+    fn banana() {
+        let text = b"banana";
+        let sa = SaIsBuilder::new().build(text);
+        assert_eq!(lcp_array(text, sa.array()), [0, 1, 3, 0, 0, 2]);
+    }
+
+    #[test]
+    fn banana_intervals() {
+        let text = b"banana";
+        let sa = SaIsBuilder::new().build(text);
+        let intervals = lcp_intervals(&lcp_array(text, sa.array()));
+        let expected = [(3, 1, 3), (1, 0, 3), (2, 4, 6), (0, 0, 6)];
+        assert_eq!(intervals.len(), expected.len());
+        for (interval, &(lcp, start, end)) in intervals.iter().zip(expected.iter()) {
+            assert_eq!(*interval, LcpInterval { lcp, start, end });
+        }
+    }
+
+    #[test]
+    fn lce_matches_naive() {
+        let text = b"abaababaabaababaababaabaababaabab";
+        let sa = SaIsBuilder::new().build(text);
+        let lce = LceIndex::new(text, sa.array());
+        let len = text.len();
+        for a in 0..=len {
+            for b in 0..=len {
+                let expected = text[a..]
+                    .iter()
+                    .zip(text[b..].iter())
+                    .take_while(|(x, y)| x == y)
+                    .count();
+                assert_eq!(lce.lce(a as TextSize, b as TextSize) as usize, expected);
+            }
+        }
+    }
+}
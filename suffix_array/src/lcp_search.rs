@@ -0,0 +1,220 @@
+// Binary search over a suffix array that avoids re-comparing the pattern from its start.
+//
+// The search keeps l = lcp(pattern, suffix left of the range) and r = lcp(pattern, suffix right of
+// the range). All suffixes in between share min(l, r) chars with the pattern, so a comparison
+// can skip them (the mlr heuristic of Manber and Myers). This is O(m log n) in the worst case.
+//
+// With the LCP-LR arrays, which hold the LCP between each middle row and the two boundaries of
+// its range, the search also decides most steps without comparing any char, and compares each
+// pattern char at most once successfully. This makes it O(m + log n).
+
+use std::ops::Range;
+
+use super::lcp::lcp_array;
+use super::suffix_array::TextSize;
+
+// Compares the suffix at pos with the pattern, whose first h chars are known to match.
+// Returns the extended lcp, and whether the suffix sorts before the boundary. The boundary is
+// before the suffixes starting with the pattern, or after them if upper is set.
+fn compare(text: &[u8], pos: TextSize, pattern: &[u8], mut h: usize, upper: bool) -> (usize, bool) {
+    let suffix = &text[pos as usize..];
+    while h < pattern.len() && h < suffix.len() && suffix[h] == pattern[h] {
+        h += 1;
+    }
+    let before = if h == pattern.len() {
+        upper
+    } else {
+        h == suffix.len() || suffix[h] < pattern[h]
+    };
+    (h, before)
+}
+
+// Returns the first row at or after the boundary, with the mlr heuristic.
+fn mlr_boundary(text: &[u8], sa: &[TextSize], pattern: &[u8], upper: bool) -> usize {
+    let (mut low, mut high) = (0, sa.len());
+    let (mut l, mut r) = (0, 0);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let (h, before) = compare(text, sa[mid], pattern, l.min(r), upper);
+        if before {
+            low = mid + 1;
+            l = h;
+        } else {
+            high = mid;
+            r = h;
+        }
+    }
+    low
+}
+
+// Returns the range of rows whose suffixes start with the pattern, with the mlr heuristic.
+pub fn mlr_find_range(text: &[u8], sa: &[TextSize], pattern: &[u8]) -> Range<usize> {
+    mlr_boundary(text, sa, pattern, false)..mlr_boundary(text, sa, pattern, true)
+}
+
+// A suffix array with its LCP-LR arrays.
+pub struct LcpLrIndex<'a> {
+    text: &'a [u8],
+    sa: &'a [TextSize],
+
+    // For the search range [low, high) whose middle row is mid:
+    // left_lcp[mid] = lcp(suffix at row low - 1, suffix at row mid), or 0 if low = 0.
+    // right_lcp[mid] = lcp(suffix at row mid, suffix at row high), or 0 if high = n.
+    left_lcp: Vec<TextSize>,
+    right_lcp: Vec<TextSize>,
+}
+
+impl<'a> LcpLrIndex<'a> {
+    pub fn new(text: &'a [u8], sa: &'a [TextSize]) -> LcpLrIndex<'a> {
+        let lcp = lcp_array(text, sa);
+        let mut index = LcpLrIndex {
+            text,
+            sa,
+            left_lcp: vec![0; sa.len()],
+            right_lcp: vec![0; sa.len()],
+        };
+        index.fill(&lcp, 0, sa.len());
+        index
+    }
+
+    // Fills the LCP-LR values of the search ranges within [low, high), and returns the lcp of the
+    // suffixes at rows low - 1 and high, or 0 if either is out of bounds.
+    fn fill(&mut self, lcp: &[TextSize], low: usize, high: usize) -> TextSize {
+        if low == high {
+            return if low > 0 && high < self.sa.len() {
+                lcp[high]
+            } else {
+                0
+            };
+        }
+        let mid = low + (high - low) / 2;
+        self.left_lcp[mid] = self.fill(lcp, low, mid);
+        self.right_lcp[mid] = self.fill(lcp, mid + 1, high);
+        self.left_lcp[mid].min(self.right_lcp[mid])
+    }
+
+    // Returns the first row at or after the boundary.
+    fn boundary(&self, pattern: &[u8], upper: bool) -> usize {
+        let (mut low, mut high) = (0, self.sa.len());
+        let (mut l, mut r) = (0, 0);
+        while low < high {
+            let mid = low + (high - low) / 2;
+
+            // The suffix at mid agrees with the pattern as far as it agrees with the closer
+            // boundary suffix. If it agrees with that suffix for longer or shorter than the
+            // pattern does, its side follows without comparing.
+            let h = if l >= r {
+                let mid_lcp = self.left_lcp[mid] as usize;
+                if mid_lcp > l {
+                    low = mid + 1;
+                    continue;
+                }
+                if mid_lcp < l {
+                    high = mid;
+                    r = mid_lcp;
+                    continue;
+                }
+                l
+            } else {
+                let mid_lcp = self.right_lcp[mid] as usize;
+                if mid_lcp > r {
+                    high = mid;
+                    continue;
+                }
+                if mid_lcp < r {
+                    low = mid + 1;
+                    l = mid_lcp;
+                    continue;
+                }
+                r
+            };
+
+            let (h, before) = compare(self.text, self.sa[mid], pattern, h, upper);
+            if before {
+                low = mid + 1;
+                l = h;
+            } else {
+                high = mid;
+                r = h;
+            }
+        }
+        low
+    }
+
+    // Returns the range of rows whose suffixes start with the pattern.
+    pub fn find_range(&self, pattern: &[u8]) -> Range<usize> {
+        self.boundary(pattern, false)..self.boundary(pattern, true)
+    }
+
+    // Returns the positions of all occurrences of the pattern, in text order.
+    pub fn locate(&self, pattern: &[u8]) -> Vec<TextSize> {
+        let mut positions: Vec<TextSize> = self.sa[self.find_range(pattern)].to_vec();
+        positions.sort_unstable();
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::search::find_range;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    fn check(text: &[u8], patterns: &[&[u8]]) {
+        let sa = SaIsBuilder::new().build(text);
+        let index = LcpLrIndex::new(text, sa.array());
+        // Empty ranges may differ in where they point.
+        let normalize = |r: Range<usize>| if r.is_empty() { 0..0 } else { r };
+        for pattern in patterns {
+            let expected = normalize(find_range(text, sa.array(), pattern));
+            assert_eq!(
+                normalize(mlr_find_range(text, sa.array(), pattern)),
+                expected
+            );
+            assert_eq!(normalize(index.find_range(pattern)), expected);
+        }
+    }
+
+    #[test]
+    fn banana() {
+        let text = b"banana";
+        let sa = SaIsBuilder::new().build(text);
+        let index = LcpLrIndex::new(text, sa.array());
+        assert_eq!(index.find_range(b""), 0..6);
+        assert_eq!(index.find_range(b"ana"), 1..3);
+        assert_eq!(index.locate(b"na"), [2, 4]);
+        assert!(index.find_range(b"nab").is_empty());
+        check(
+            text,
+            &[b"a", b"an", b"banana", b"bananas", b"c", b"", b"nana"],
+        );
+    }
+
+    #[test]
+    fn matches_search() {
+        let mut rand: usize = 23;
+        let mut random_text = |len: usize, alphabet: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % alphabet) as u8)
+                .collect()
+        };
+        for n in 0..40 {
+            let text = random_text(50 + n * 5, 2 + n % 3);
+            let mut patterns: Vec<Vec<u8>> = Vec::new();
+            for i in 0..20 {
+                let start = (i * 7) % text.len();
+                let end = (start + 1 + i % 9).min(text.len());
+                patterns.push(text[start..end].to_vec());
+                patterns.push(random_text(1 + i % 6, 2 + n % 3));
+            }
+            let patterns: Vec<&[u8]> = patterns.iter().map(|p| p.as_slice()).collect();
+            check(&text, &patterns);
+        }
+
+        // Long patterns over a repetitive text.
+        let text = [b"ab".repeat(200), b"c".to_vec()].concat();
+        check(&text, &[&text[100..300], &text[1..], &b"ab".repeat(201)]);
+    }
+}
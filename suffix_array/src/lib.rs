@@ -1,9 +1,63 @@
+pub mod approximate;
+pub mod autocomplete;
+pub mod bbwt;
+pub mod bitvector;
+pub mod bwt;
+pub mod circular;
+pub mod compress;
+pub mod context;
+pub mod csa;
+pub mod dna;
+mod errors;
+pub mod fasta;
+pub mod folding;
+mod huffman;
+pub mod lcp;
+pub mod lcp_search;
+pub mod matching_statistics;
 pub mod naive;
+pub mod records;
+pub mod regex;
+pub mod runs;
 pub mod sa_is;
+pub mod search;
+pub mod sparse;
+pub mod substrings;
 pub mod suffix_array;
 mod testing;
+pub mod utf8;
+pub mod wavelet;
+pub mod wildcard;
+pub mod words;
 
+pub use approximate::{edit_distance_search, hamming_search, ApproximateMatch};
+pub use autocomplete::AutocompleteIndex;
+pub use bbwt::{bbwt, inverse_bbwt, lyndon_factorization};
+pub use bitvector::BitVector;
+pub use bwt::Bwt;
+pub use circular::CircularBuilder;
+pub use compress::BlockCompressor;
+pub use context::{keyword_in_context, Hit, HitOrder, LineIndex};
+pub use csa::CompressedSuffixArray;
+pub use dna::{locate_both_strands, reverse_complement, DnaBuilder, MemoryReport, PackedDna};
+pub use dna::{Strand, StrandHit};
+pub use fasta::{read_sequences, SequenceIndex, SequenceRecord};
+pub use folding::{CaseFolding, FoldedIndex, FoldedText};
+pub use lcp::{lcp_array, LceIndex};
+pub use lcp_search::{mlr_find_range, LcpLrIndex};
+pub use matching_statistics::{MatchingStatistic, MatchingStatisticsIndex};
 pub use naive::NaiveBuilder;
+pub use records::{locate_within_records, RecordIndex, RecordPosition};
+pub use regex::{regex_search, Regex};
+pub use runs::{compute_runs, tandem_repeats, Run};
 pub use sa_is::SaIsBuilder;
+pub use sparse::{SparseBuilder, SparseSuffixArray};
+pub use substrings::{minimal_absent_words, shortest_unique_substrings, unique_prefix_lengths};
+pub use suffix_array::SuffixArray;
 pub use suffix_array::SuffixArrayBuilder;
-pub use suffix_array::validate_suffix_array;
\ No newline at end of file
+pub use suffix_array::inverse_suffix_array;
+pub use suffix_array::validate_suffix_array;
+pub use utf8::Utf8Builder;
+pub use wavelet::WaveletMatrix;
+pub use wildcard::{wildcard_search, WildcardPattern};
+pub use words::{DefaultTokenizer, Tokenizer, WordSuffixArray};
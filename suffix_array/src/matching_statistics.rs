@@ -0,0 +1,188 @@
+// Matching statistics of a query against an indexed text.
+//
+// For each pos i of the query, the matching statistic is the length of the longest prefix of
+// query[i..] that occurs in the text, and a position where it occurs.
+// e.g. against the text "banana", the lengths for the query "anaban" are [3, 2, 1, 3, 2, 1].
+//
+// After matching query[i..i + l] at text pos p, query[i + 1..i + l] occurs at p + 1. So the
+// match for the next pos resumes from the suffix array row of p + 1, after widening it to all
+// the rows sharing (l - 1) chars, using range minimum queries over the LCP array.
+
+use std::ops::Range;
+
+use super::lcp::{lcp_array_with_inverse, RangeMin};
+use super::search::narrow_range;
+use super::suffix_array::{inverse_suffix_array, TextSize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MatchingStatistic {
+    // Length of the longest prefix of the query suffix that occurs in the text.
+    pub length: TextSize,
+
+    // A text pos where that prefix occurs. This is 0 if length is 0.
+    pub pos: TextSize,
+}
+
+// A text and its suffix array, indexed for matching statistics.
+pub struct MatchingStatisticsIndex<'a> {
+    text: &'a [u8],
+    sa: &'a [TextSize],
+    inverse_sa: Vec<TextSize>,
+    lcp_min: RangeMin,
+}
+
+impl<'a> MatchingStatisticsIndex<'a> {
+    pub fn new(text: &'a [u8], sa: &'a [TextSize]) -> MatchingStatisticsIndex<'a> {
+        let inverse_sa = inverse_suffix_array(sa);
+        let lcp = lcp_array_with_inverse(text, sa, &inverse_sa);
+        MatchingStatisticsIndex {
+            text,
+            sa,
+            inverse_sa,
+            lcp_min: RangeMin::new(&lcp),
+        }
+    }
+
+    // Returns an iterator of the matching statistics of each query pos, in query order.
+    pub fn matching_statistics<'b>(&'b self, query: &'b [u8]) -> MatchingStatistics<'a, 'b> {
+        MatchingStatistics {
+            index: self,
+            query,
+            query_pos: 0,
+            last: MatchingStatistic { length: 0, pos: 0 },
+        }
+    }
+
+    // Returns the widest range of rows around row, whose suffixes share a prefix of length
+    // depth with the suffix at row.
+    fn widen_range(&self, row: usize, depth: TextSize) -> Range<usize> {
+        // Binary search for lcp[k] >= depth for all k in (start, row] and (row, end).
+        let (mut low, mut high) = (0, row);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.lcp_min.min(mid + 1, row + 1) < depth {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let start = low;
+
+        let (mut low, mut high) = (row + 1, self.sa.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if self.lcp_min.min(row + 1, mid) >= depth {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        start..low
+    }
+}
+
+// Iterates through the matching statistics of a query.
+pub struct MatchingStatistics<'a, 'b> {
+    index: &'b MatchingStatisticsIndex<'a>,
+    query: &'b [u8],
+
+    // The next query pos.
+    query_pos: usize,
+
+    // The matching statistic of the previous query pos.
+    last: MatchingStatistic,
+}
+
+impl<'a, 'b> Iterator for MatchingStatistics<'a, 'b> {
+    type Item = MatchingStatistic;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.index;
+        if self.query_pos == self.query.len() {
+            return None;
+        }
+        if index.sa.is_empty() {
+            self.query_pos += 1;
+            return Some(self.last);
+        }
+
+        // Resume from the previous match, minus its first char.
+        let (mut range, mut length) = if self.last.length > 1 {
+            let length = self.last.length - 1;
+            let row = index.inverse_sa[(self.last.pos + 1) as usize] as usize;
+            (index.widen_range(row, length), length)
+        } else {
+            (0..index.sa.len(), 0)
+        };
+
+        // Extend the match one char at a time.
+        while self.query_pos + (length as usize) < self.query.len() {
+            let ch = self.query[self.query_pos + length as usize];
+            let narrowed = narrow_range(index.text, index.sa, range.clone(), length as usize, ch);
+            if narrowed.is_empty() {
+                break;
+            }
+            range = narrowed;
+            length += 1;
+        }
+
+        let pos = if length > 0 { index.sa[range.start] } else { 0 };
+        self.last = MatchingStatistic { length, pos };
+        self.query_pos += 1;
+        Some(self.last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    // Naively computes the matching statistic lengths.
+    fn naive_lengths(text: &[u8], query: &[u8]) -> Vec<TextSize> {
+        (0..query.len())
+            .map(|i| {
+                (0..=query.len() - i)
+                    .rev()
+                    .find(|&l| l == 0 || text.windows(l).any(|w| w == &query[i..i + l]))
+                    .unwrap() as TextSize
+            })
+            .collect()
+    }
+
+    fn check(text: &[u8], query: &[u8]) {
+        let sa = SaIsBuilder::new().build(text);
+        let index = MatchingStatisticsIndex::new(text, sa.array());
+        let stats: Vec<MatchingStatistic> = index.matching_statistics(query).collect();
+        let lengths: Vec<TextSize> = stats.iter().map(|s| s.length).collect();
+        assert_eq!(lengths, naive_lengths(text, query));
+        for (i, s) in stats.iter().enumerate() {
+            let (pos, length) = (s.pos as usize, s.length as usize);
+            assert_eq!(text[pos..pos + length], query[i..i + length]);
+        }
+    }
+
+    #[test]
+    fn banana() {
+        check(b"banana", b"anaban");
+        check(b"banana", b"xbananax");
+        check(b"banana", b"");
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rand: usize = 11;
+        let mut random_text = |len: usize, alphabet: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % alphabet) as u8)
+                .collect()
+        };
+        for n in 0..50 {
+            let text = random_text(10 + n * 3, 2 + n % 3);
+            let query = random_text(20 + n, 2 + n % 4);
+            check(&text, &query);
+        }
+    }
+}
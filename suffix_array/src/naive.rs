@@ -8,6 +8,12 @@ impl NaiveBuilder {
     }
 }
 
+impl Default for NaiveBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 struct NaiveSuffixArray {
     sa: Vec<TextSize>,
 }
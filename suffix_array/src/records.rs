@@ -0,0 +1,190 @@
+// Records of a text, separated by a delimiter byte such as '\n' for the lines of a log file.
+//
+// A record includes its delimiter, if any. e.g. the records of "ab\ncd" are "ab\n" and "cd".
+// Positions within records are (record, offset) pairs, which both start from 0.
+
+use std::ops::Range;
+
+use super::search::find_range;
+use super::suffix_array::TextSize;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RecordPosition {
+    pub record: usize,
+    pub offset: usize,
+}
+
+// The start positions of the records of a text.
+pub struct RecordIndex {
+    starts: Vec<usize>,
+    text_len: usize,
+}
+
+impl RecordIndex {
+    pub fn new(text: &[u8], delimiter: u8) -> RecordIndex {
+        let delimiters = text.iter().enumerate().filter(|(_, &ch)| ch == delimiter);
+        let mut starts: Vec<usize> = std::iter::once(0)
+            .chain(delimiters.map(|(pos, _)| pos + 1))
+            .collect();
+        // No empty record after a final delimiter, or in an empty text.
+        if starts.last() == Some(&text.len()) {
+            starts.pop();
+        }
+        RecordIndex {
+            starts,
+            text_len: text.len(),
+        }
+    }
+
+    // The records are the lines of the text.
+    pub fn lines(text: &[u8]) -> RecordIndex {
+        RecordIndex::new(text, b'\n')
+    }
+
+    // Number of records.
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    // Returns the record that contains a text position, or None if the position is past the end
+    // of the text.
+    pub fn record(&self, pos: usize) -> Option<usize> {
+        if pos >= self.text_len {
+            return None;
+        }
+        // The first record starts at 0, since the text is not empty.
+        Some(self.starts.partition_point(|&start| start <= pos) - 1)
+    }
+
+    pub fn position(&self, pos: usize) -> Option<RecordPosition> {
+        let record = self.record(pos)?;
+        Some(RecordPosition {
+            record,
+            offset: pos - self.starts[record],
+        })
+    }
+
+    // Returns the range of text positions of a record, including its delimiter.
+    pub fn range(&self, record: usize) -> Range<usize> {
+        let end = self
+            .starts
+            .get(record + 1)
+            .copied()
+            .unwrap_or(self.text_len);
+        self.starts[record]..end
+    }
+
+    // Whether text[range] is within a single record.
+    pub fn contains(&self, range: Range<usize>) -> bool {
+        range.is_empty()
+            || self
+                .record(range.start)
+                .is_some_and(|record| self.range(record).end >= range.end)
+    }
+}
+
+// Returns the positions of the occurrences of the pattern that do not cross a record boundary,
+// in text order.
+pub fn locate_within_records(
+    text: &[u8],
+    sa: &[TextSize],
+    pattern: &[u8],
+    records: &RecordIndex,
+) -> Vec<RecordPosition> {
+    let mut positions: Vec<usize> = sa[find_range(text, sa, pattern)]
+        .iter()
+        .map(|&pos| pos as usize)
+        .filter(|&pos| records.contains(pos..pos + pattern.len()))
+        .collect();
+    positions.sort_unstable();
+    positions
+        .into_iter()
+        .map(|pos| records.position(pos).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    #[test]
+    fn lines() {
+        let text = b"ab\ncab\n\nabc\n";
+        let records = RecordIndex::lines(text);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records.range(0), 0..3);
+        assert_eq!(records.range(2), 7..8);
+        assert_eq!(records.range(3), 8..12);
+        assert_eq!(records.record(2), Some(0));
+        assert_eq!(records.record(3), Some(1));
+        assert_eq!(records.record(12), None);
+        assert_eq!(
+            records.position(9),
+            Some(RecordPosition {
+                record: 3,
+                offset: 1
+            })
+        );
+        assert!(records.contains(4..7));
+        assert!(!records.contains(4..8));
+
+        let sa = SaIsBuilder::new().build(text);
+        let found = |pattern: &[u8]| -> Vec<(usize, usize)> {
+            locate_within_records(text, sa.array(), pattern, &records)
+                .iter()
+                .map(|p| (p.record, p.offset))
+                .collect()
+        };
+        assert_eq!(found(b"ab"), [(0, 0), (1, 1), (3, 0)]);
+        assert_eq!(found(b"ab\n"), [(0, 0), (1, 1)]);
+        assert!(found(b"b\nc").is_empty());
+        assert!(found(b"\n\n").is_empty());
+    }
+
+    #[test]
+    fn empty() {
+        let text = b"";
+        let records = RecordIndex::lines(text);
+        assert!(records.is_empty());
+        assert_eq!(records.record(0), None);
+        assert_eq!(records.position(0), None);
+        assert!(records.contains(0..0));
+        assert!(!records.contains(0..1));
+
+        assert!(locate_within_records(text, &[], b"x", &records).is_empty());
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rand: usize = 5;
+        let mut random_text = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| b"ab;"[testing::next_random(&mut rand) % 3])
+                .collect()
+        };
+        for n in 0..20 {
+            let text = random_text(50 + n * 10);
+            let sa = SaIsBuilder::new().build(&text);
+            let records = RecordIndex::new(&text, b';');
+            for pattern in [random_text(2), random_text(4)] {
+                let expected: Vec<usize> = (0..text.len())
+                    .filter(|&pos| text[pos..].starts_with(&pattern))
+                    .filter(|&pos| !text[pos..pos + pattern.len() - 1].contains(&b';'))
+                    .collect();
+                let found: Vec<usize> =
+                    locate_within_records(&text, sa.array(), &pattern, &records)
+                        .iter()
+                        .map(|p| records.range(p.record).start + p.offset)
+                        .collect();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,741 @@
+// Regular expression search accelerated by the suffix array.
+//
+// Supported syntax (a small subset):
+//   abc        literal bytes.
+//   .          any byte except '\n'.
+//   [a-z]      byte classes, with the same syntax as in wildcard.rs.
+//   \d \w \s   digits, word bytes [0-9A-Za-z_], and whitespace.
+//   \c         the byte c literally, e.g. \. or \(.
+//   x* x+ x?   repetitions.
+//   x{m} x{m,} x{m,n}
+//   a|b        alternation.
+//   (...)      grouping.
+//
+// A search first extracts literals from the regex, such that every match contains one of them
+// within a bounded offset from its start. e.g. every match of "(ERROR|WARN) [0-9]+" starts with
+// "ERROR " or "WARN ". The occurrences of the literals, found with the suffix array, give the
+// candidate start positions, and each candidate is verified by running the regex.
+// If no such literals exist, e.g. for "[a-z]+", every position is a candidate.
+
+use std::ops::Range;
+
+use super::search::find_range;
+use super::suffix_array::TextSize;
+use super::wildcard::{parse_byte_class, ByteClass, PatternError};
+
+// Largest number of strings kept in a literal set.
+const MAX_LITERALS: usize = 32;
+
+// Largest count in {m,n}, and largest number of instructions of a compiled regex. Repetitions are
+// compiled by copying their operand, so nested repetitions multiply the size of the program.
+const MAX_REPEAT: usize = 1000;
+const MAX_PROGRAM: usize = 1 << 20;
+
+// Largest nesting depth of groups, and largest height of the parsed tree. The parser and the
+// passes over the tree recurse, so deeper regexes would overflow the stack.
+const MAX_DEPTH: usize = 200;
+
+// Parsed regex.
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Class(ByteClass),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+// Recursive descent parser.
+struct Parser<'a> {
+    pattern: &'a [u8],
+    pos: usize,
+
+    // Number of groups open at pos.
+    depth: usize,
+
+    // Height of the last parsed node.
+    height: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &'static str) -> PatternError {
+        PatternError {
+            pos: self.pos,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.pattern.get(self.pos).copied()
+    }
+
+    // Sets the height of the last parsed node.
+    fn set_height(&mut self, height: usize) -> Result<(), PatternError> {
+        if height > MAX_DEPTH {
+            return Err(self.error("regex nested too deeply"));
+        }
+        self.height = height;
+        Ok(())
+    }
+
+    // Returns a node over the nodes, with the given height if there is more than one.
+    fn join(
+        &mut self,
+        mut nodes: Vec<Node>,
+        height: usize,
+        join: fn(Vec<Node>) -> Node,
+    ) -> Result<Node, PatternError> {
+        Ok(match nodes.len() {
+            0 => {
+                self.height = 1;
+                Node::Empty
+            }
+            1 => nodes.pop().unwrap(),
+            _ => {
+                self.set_height(height + 1)?;
+                join(nodes)
+            }
+        })
+    }
+
+    // alternation := concat ('|' concat)*
+    fn parse_alternation(&mut self) -> Result<Node, PatternError> {
+        let mut branches: Vec<Node> = vec![self.parse_concat()?];
+        let mut height = self.height;
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            branches.push(self.parse_concat()?);
+            height = height.max(self.height);
+        }
+        self.height = height;
+        self.join(branches, height, Node::Alternate)
+    }
+
+    // concat := repeat*
+    fn parse_concat(&mut self) -> Result<Node, PatternError> {
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut height = 0;
+        while let Some(ch) = self.peek() {
+            if ch == b'|' || ch == b')' {
+                break;
+            }
+            nodes.push(self.parse_repeat()?);
+            height = height.max(self.height);
+        }
+        self.height = height;
+        self.join(nodes, height, Node::Concat)
+    }
+
+    // repeat := atom ('*' | '+' | '?' | '{m}' | '{m,}' | '{m,n}')*
+    fn parse_repeat(&mut self) -> Result<Node, PatternError> {
+        let mut node = self.parse_atom()?;
+        loop {
+            let (min, max) = match self.peek() {
+                Some(b'*') => (0, None),
+                Some(b'+') => (1, None),
+                Some(b'?') => (0, Some(1)),
+                Some(b'{') => self.parse_bounds()?,
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+            self.set_height(self.height + 1)?;
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+            };
+        }
+    }
+
+    // Parses {m}, {m,} or {m,n}, leaving pos at the closing '}'.
+    fn parse_bounds(&mut self) -> Result<(usize, Option<usize>), PatternError> {
+        let close = self.pattern[self.pos..]
+            .iter()
+            .position(|&ch| ch == b'}')
+            .ok_or_else(|| self.error("unterminated repetition"))?;
+        let bounds = std::str::from_utf8(&self.pattern[self.pos + 1..self.pos + close])
+            .map_err(|_| self.error("invalid repetition"))?;
+        let number = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| self.error("invalid repetition"))
+        };
+        let (min, max) = match bounds.split_once(',') {
+            None => (number(bounds)?, Some(number(bounds)?)),
+            Some((min, "")) => (number(min)?, None),
+            Some((min, max)) => (number(min)?, Some(number(max)?)),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("invalid repetition"));
+        }
+        if max.unwrap_or(min) > MAX_REPEAT {
+            return Err(self.error("repetition count too large"));
+        }
+        self.pos += close;
+        Ok((min, max))
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, PatternError> {
+        let ch = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        let class = match ch {
+            b'(' => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("regex nested too deeply"));
+                }
+                self.depth += 1;
+                let node = self.parse_alternation()?;
+                if self.peek() != Some(b')') {
+                    return Err(self.error("missing ')'"));
+                }
+                self.depth -= 1;
+                self.pos += 1;
+                return Ok(node);
+            }
+            b'*' | b'+' | b'?' | b'{' => {
+                self.pos -= 1;
+                return Err(self.error("repetition without an operand"));
+            }
+            b'[' => {
+                let (class, next) = parse_byte_class(self.pattern, self.pos)?;
+                self.pos = next;
+                class
+            }
+            b'.' => ByteClass::byte(b'\n').negate(),
+            b'\\' => {
+                let escaped = self
+                    .peek()
+                    .ok_or_else(|| self.error("trailing backslash"))?;
+                self.pos += 1;
+                match escaped {
+                    b'd' => byte_range_class(&[(b'0', b'9')]),
+                    b'w' => {
+                        byte_range_class(&[(b'0', b'9'), (b'A', b'Z'), (b'a', b'z'), (b'_', b'_')])
+                    }
+                    b's' => byte_range_class(&[(b' ', b' '), (b'\t', b'\r')]),
+                    _ => ByteClass::byte(escaped),
+                }
+            }
+            _ => ByteClass::byte(ch),
+        };
+        self.height = 1;
+        Ok(Node::Class(class))
+    }
+}
+
+fn byte_range_class(ranges: &[(u8, u8)]) -> ByteClass {
+    let mut class = ByteClass::empty();
+    for &(start, end) in ranges {
+        for ch in start..=end {
+            class.insert(ch);
+        }
+    }
+    class
+}
+
+// Literals at a bounded offset: every match contains one of the strings, starting at most offset
+// bytes from the start of the match.
+type LiteralSet = (Vec<Vec<u8>>, usize);
+
+// Literal information about the strings matched by a node.
+#[derive(Clone, Debug)]
+struct Literals {
+    // Maximum length of a match, or None if unbounded.
+    max_len: Option<usize>,
+
+    // All the strings matched by the node, if there are only a few.
+    exact: Option<Vec<Vec<u8>>>,
+
+    // Every match ends with one of the strings, at the offset. This lets literal runs grow across
+    // the nodes of a concatenation.
+    suffix: Option<LiteralSet>,
+
+    // Non-empty literals such that every match contains one of them.
+    required: Option<LiteralSet>,
+}
+
+// Concatenates each string of x with each string of y, if there are only a few.
+fn cross(x: &[Vec<u8>], y: &[Vec<u8>]) -> Option<Vec<Vec<u8>>> {
+    if x.len() * y.len() > MAX_LITERALS {
+        return None;
+    }
+    Some(
+        x.iter()
+            .flat_map(|s| y.iter().map(move |t| [s.as_slice(), t].concat()))
+            .collect(),
+    )
+}
+
+// Unions two literal sets, if there are only a few strings.
+fn union(x: Option<LiteralSet>, y: Option<LiteralSet>) -> Option<LiteralSet> {
+    match (x, y) {
+        (Some((mut x, x_offset)), Some((y, y_offset))) if x.len() + y.len() <= MAX_LITERALS => {
+            x.extend(y);
+            Some((x, x_offset.max(y_offset)))
+        }
+        _ => None,
+    }
+}
+
+impl Literals {
+    // Prefers longer literals, then fewer of them. Empty literals are never used.
+    fn best(a: Option<LiteralSet>, b: Option<LiteralSet>) -> Option<LiteralSet> {
+        let score = |r: &LiteralSet| {
+            let min_len = r.0.iter().map(|l| l.len()).min().unwrap_or(0);
+            (min_len, std::cmp::Reverse(r.0.len()))
+        };
+        let a = a.filter(|r| score(r).0 > 0);
+        let b = b.filter(|r| score(r).0 > 0);
+        match (a, b) {
+            (Some(a), Some(b)) => Some(if score(&b) > score(&a) { b } else { a }),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn new(
+        max_len: Option<usize>,
+        exact: Option<Vec<Vec<u8>>>,
+        suffix: Option<LiteralSet>,
+        required: Option<LiteralSet>,
+    ) -> Literals {
+        let suffix = match &exact {
+            Some(strings) => Some((strings.clone(), 0)),
+            None => suffix,
+        };
+        let required = Self::best(suffix.clone(), required);
+        Literals {
+            max_len,
+            exact,
+            suffix,
+            required,
+        }
+    }
+
+    fn of(node: &Node) -> Literals {
+        match node {
+            Node::Empty => Literals::new(Some(0), Some(vec![Vec::new()]), None, None),
+            Node::Class(class) => {
+                let bytes: Vec<Vec<u8>> = (0..=255u8)
+                    .filter(|&ch| class.contains(ch))
+                    .map(|ch| vec![ch])
+                    .collect();
+                let exact = if bytes.len() <= 4 { Some(bytes) } else { None };
+                Literals::new(Some(1), exact, None, None)
+            }
+            Node::Concat(nodes) => nodes
+                .iter()
+                .map(Literals::of)
+                .reduce(Literals::concat)
+                .unwrap(),
+            Node::Alternate(nodes) => nodes
+                .iter()
+                .map(Literals::of)
+                .reduce(Literals::alternate)
+                .unwrap(),
+            Node::Repeat { node, min, max } => {
+                let inner = Literals::of(node);
+                let max_len = match (inner.max_len, max) {
+                    (Some(l), Some(m)) => l.checked_mul(*m),
+                    _ => None,
+                };
+                let exact = match (&inner.exact, min, max) {
+                    (Some(strings), 0, Some(1)) if strings.len() < MAX_LITERALS => {
+                        let mut strings = strings.clone();
+                        strings.push(Vec::new());
+                        Some(strings)
+                    }
+                    (exact, 1, Some(1)) => exact.clone(),
+                    _ => None,
+                };
+                // The first repetition contains the required literals of the inner node.
+                let required = if *min >= 1 { inner.required } else { None };
+                Literals::new(max_len, exact, None, required)
+            }
+        }
+    }
+
+    fn concat(a: Literals, b: Literals) -> Literals {
+        let max_len = a.max_len.zip(b.max_len).and_then(|(x, y)| x.checked_add(y));
+        let exact = match (&a.exact, &b.exact) {
+            (Some(x), Some(y)) => cross(x, y),
+            _ => None,
+        };
+        // The strings of b start after the match of a.
+        let shift = |set: Option<LiteralSet>| match (set, a.max_len) {
+            (Some((strings, offset)), Some(len)) => Some((strings, offset.checked_add(len)?)),
+            _ => None,
+        };
+        let suffix = match (&a.suffix, &b.exact) {
+            (Some((x, offset)), Some(y)) => cross(x, y)
+                .map(|strings| (strings, *offset))
+                .or_else(|| shift(b.suffix.clone())),
+            _ => shift(b.suffix.clone()),
+        };
+        let required = Self::best(a.required.clone(), shift(b.required));
+        Literals::new(max_len, exact, suffix, required)
+    }
+
+    fn alternate(a: Literals, b: Literals) -> Literals {
+        let max_len = a.max_len.zip(b.max_len).map(|(x, y)| x.max(y));
+        let exact = match (a.exact, b.exact) {
+            (Some(mut x), Some(y)) if x.len() + y.len() <= MAX_LITERALS => {
+                x.extend(y);
+                Some(x)
+            }
+            _ => None,
+        };
+        // Each branch must contain one of its literals.
+        let suffix = union(a.suffix, b.suffix);
+        let required = union(a.required, b.required);
+        Literals::new(max_len, exact, suffix, required)
+    }
+}
+
+// Instructions of a Pike VM, which simulates the NFA of the regex.
+#[derive(Clone, Debug)]
+enum Instruction {
+    // Consumes a byte in the class.
+    Byte(ByteClass),
+
+    // Continues at both instructions.
+    Split(usize, usize),
+
+    Jump(usize),
+
+    Match,
+}
+
+// Returns the number of instructions of the compiled node, or None if it overflows.
+fn program_size(node: &Node) -> Option<usize> {
+    match node {
+        Node::Empty => Some(0),
+        Node::Class(_) => Some(1),
+        Node::Concat(nodes) => nodes
+            .iter()
+            .try_fold(0usize, |size, node| size.checked_add(program_size(node)?)),
+        // A split and a jump for each branch but the last.
+        Node::Alternate(nodes) => nodes.iter().try_fold(0usize, |size, node| {
+            size.checked_add(program_size(node)?)?.checked_add(2)
+        }),
+        Node::Repeat { node, min, max } => {
+            let size = program_size(node)?;
+            let copies = max.unwrap_or(*min + 1);
+            // A split for each optional copy, and a jump for a star.
+            size.checked_add(1)?.checked_mul(copies)?.checked_add(1)
+        }
+    }
+}
+
+fn compile(node: &Node, program: &mut Vec<Instruction>) {
+    match node {
+        Node::Empty => {}
+        Node::Class(class) => program.push(Instruction::Byte(*class)),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program);
+            }
+        }
+        Node::Alternate(nodes) => {
+            // split L1, next; L1: a; jump end; next: split L2, next2; ...
+            let mut jumps: Vec<usize> = Vec::new();
+            for (i, node) in nodes.iter().enumerate() {
+                if i + 1 < nodes.len() {
+                    let split = program.len();
+                    program.push(Instruction::Split(split + 1, 0));
+                    compile(node, program);
+                    jumps.push(program.len());
+                    program.push(Instruction::Jump(0));
+                    let next = program.len();
+                    program[split] = Instruction::Split(split + 1, next);
+                } else {
+                    compile(node, program);
+                }
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Instruction::Jump(end);
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, program);
+            }
+            match max {
+                // Star: L: split body, end; body; jump L.
+                None => {
+                    let split = program.len();
+                    program.push(Instruction::Split(split + 1, 0));
+                    compile(node, program);
+                    program.push(Instruction::Jump(split));
+                    let end = program.len();
+                    program[split] = Instruction::Split(split + 1, end);
+                }
+                // Optional copies: split body, end; body; ...
+                Some(max) => {
+                    let mut splits: Vec<usize> = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Instruction::Split(0, 0));
+                        compile(node, program);
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = Instruction::Split(split + 1, end);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct Regex {
+    program: Vec<Instruction>,
+    required: Option<(Vec<Vec<u8>>, usize)>,
+}
+
+impl Regex {
+    pub fn parse(pattern: &[u8]) -> Result<Regex, PatternError> {
+        let mut parser = Parser {
+            pattern,
+            pos: 0,
+            depth: 0,
+            height: 0,
+        };
+        let node = parser.parse_alternation()?;
+        if parser.pos < pattern.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+
+        if program_size(&node).is_none_or(|size| size >= MAX_PROGRAM) {
+            return Err(PatternError {
+                pos: 0,
+                message: "regex too large",
+            });
+        }
+        let mut program: Vec<Instruction> = Vec::new();
+        compile(&node, &mut program);
+        program.push(Instruction::Match);
+        Ok(Regex {
+            program,
+            required: Literals::of(&node).required,
+        })
+    }
+
+    // Returns the literals that every match contains, within a bounded offset from its start.
+    pub fn required_literals(&self) -> Option<&[Vec<u8>]> {
+        self.required
+            .as_ref()
+            .map(|(literals, _)| literals.as_slice())
+    }
+
+    // Returns the end of the longest match starting at start, if any.
+    pub fn longest_match_at(&self, text: &[u8], start: usize) -> Option<usize> {
+        let mut threads: Vec<usize> = Vec::new();
+        let mut next_threads: Vec<usize> = Vec::new();
+        // added[pc] = the step at which pc was last added, to avoid duplicate threads.
+        let mut added: Vec<usize> = vec![usize::MAX; self.program.len()];
+        let mut longest: Option<usize> = None;
+
+        self.add_thread(0, 0, &mut added, &mut threads);
+        let mut pos = start;
+        loop {
+            if threads
+                .iter()
+                .any(|&pc| matches!(self.program[pc], Instruction::Match))
+            {
+                longest = Some(pos);
+            }
+            if pos == text.len() || threads.is_empty() {
+                break;
+            }
+            let step = pos - start + 1;
+            for &pc in threads.iter() {
+                if let Instruction::Byte(class) = &self.program[pc] {
+                    if class.contains(text[pos]) {
+                        self.add_thread(pc + 1, step, &mut added, &mut next_threads);
+                    }
+                }
+            }
+            std::mem::swap(&mut threads, &mut next_threads);
+            next_threads.clear();
+            pos += 1;
+        }
+        longest
+    }
+
+    // Adds a thread and follows its jumps and splits, with an explicit stack, since a regex can
+    // have long chains of them.
+    fn add_thread(&self, pc: usize, step: usize, added: &mut [usize], threads: &mut Vec<usize>) {
+        let mut stack: Vec<usize> = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if added[pc] == step {
+                continue;
+            }
+            added[pc] = step;
+            match self.program[pc] {
+                Instruction::Jump(target) => stack.push(target),
+                // b is pushed first, so that a is followed first.
+                Instruction::Split(a, b) => {
+                    stack.push(b);
+                    stack.push(a);
+                }
+                _ => threads.push(pc),
+            }
+        }
+    }
+
+    // Finds the non-empty matches among the candidate start positions, in increasing order.
+    // Matches are the longest at their start, and do not overlap.
+    fn find_at(&self, text: &[u8], candidates: impl Iterator<Item = usize>) -> Vec<Range<usize>> {
+        let mut matches: Vec<Range<usize>> = Vec::new();
+        let mut next_start = 0;
+        for start in candidates {
+            if start < next_start {
+                continue;
+            }
+            if let Some(end) = self.longest_match_at(text, start) {
+                if end > start {
+                    matches.push(start..end);
+                    next_start = end;
+                }
+            }
+        }
+        matches
+    }
+
+    // Finds the matches by trying every start position.
+    pub fn find_all(&self, text: &[u8]) -> Vec<Range<usize>> {
+        self.find_at(text, 0..text.len())
+    }
+}
+
+// Finds the non-empty, non-overlapping, leftmost-longest matches of the regex, using the suffix
+// array to find the candidate start positions. Returns the same matches as Regex::find_all().
+pub fn regex_search(text: &[u8], sa: &[TextSize], regex: &Regex) -> Vec<Range<usize>> {
+    let (literals, max_offset) = match &regex.required {
+        Some(required) => required,
+        None => return regex.find_all(text),
+    };
+
+    // A literal at pos means a match may start in [pos - max_offset, pos].
+    let mut occurrences: Vec<usize> = Vec::new();
+    for literal in literals {
+        let range = find_range(text, sa, literal);
+        occurrences.extend(sa[range].iter().map(|&pos| pos as usize));
+    }
+    occurrences.sort_unstable();
+    occurrences.dedup();
+
+    let mut candidates: Vec<usize> = Vec::new();
+    for pos in occurrences {
+        let first = pos.saturating_sub(*max_offset);
+        let first = first.max(candidates.last().map_or(0, |&last| last + 1));
+        candidates.extend(first..=pos);
+    }
+    regex.find_at(text, candidates.into_iter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    fn spans<'a>(text: &'a [u8], matches: &[Range<usize>]) -> Vec<&'a [u8]> {
+        matches.iter().map(|r| &text[r.clone()]).collect()
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Regex::parse(b"(ab").is_err());
+        assert!(Regex::parse(b"ab)").is_err());
+        assert!(Regex::parse(b"*a").is_err());
+        assert!(Regex::parse(b"a{3,1}").is_err());
+        assert!(Regex::parse(b"a{x}").is_err());
+        assert!(Regex::parse(b"[ab").is_err());
+        assert!(Regex::parse(b"a{1001}").is_err());
+        assert!(Regex::parse(b"a{2,99999999999999999999}").is_err());
+        assert!(Regex::parse(b"((a{1000}){1000}){1000}").is_err());
+    }
+
+    #[test]
+    fn nesting() {
+        let nested = |depth: usize, inner: &[u8]| -> Vec<u8> {
+            [vec![b'('; depth], inner.to_vec(), vec![b')'; depth]].concat()
+        };
+        assert!(Regex::parse(&nested(100000, b"a")).is_err());
+        assert!(Regex::parse(&vec![b'('; 100000]).is_err());
+        assert!(Regex::parse(&nested(MAX_DEPTH + 1, b"a")).is_err());
+        let regex = Regex::parse(&nested(MAX_DEPTH, b"ab|c")).unwrap();
+        assert_eq!(regex.longest_match_at(b"ab", 0), Some(2));
+
+        // Stacked repetitions also make the tree deeper, but long sequences do not.
+        assert!(Regex::parse(&[&b"a"[..], &vec![b'?'; 100000]].concat()).is_err());
+        assert!(Regex::parse("(a?)".repeat(10000).as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn large_repetitions() {
+        let text = vec![b'a'; 3000];
+        let regex = Regex::parse(b"(a{1000}){2}a{0,1000}").unwrap();
+        assert_eq!(regex.longest_match_at(&text, 0), Some(3000));
+        assert_eq!(regex.longest_match_at(&text, 1000), Some(3000));
+        assert_eq!(regex.longest_match_at(&text, 1001), None);
+
+        // Long chains of splits.
+        let regex = Regex::parse(b"(a?){1000}b").unwrap();
+        assert_eq!(regex.longest_match_at(b"aaab", 0), Some(4));
+        assert_eq!(regex.longest_match_at(b"aaa", 0), None);
+    }
+
+    #[test]
+    fn required_literals() {
+        let literals = |pattern: &[u8]| -> Option<Vec<Vec<u8>>> {
+            Regex::parse(pattern)
+                .unwrap()
+                .required_literals()
+                .map(|l| l.to_vec())
+        };
+        let strings = |s: &[&str]| Some(s.iter().map(|s| s.as_bytes().to_vec()).collect());
+        assert_eq!(
+            literals(b"(ERROR|WARN) [0-9]+"),
+            strings(&["ERROR ", "WARN "])
+        );
+        assert_eq!(literals(b"[0-9]{2}:[0-9]{2} disk"), strings(&[" disk"]));
+        assert_eq!(literals(b"colou?r"), strings(&["colour", "color"]));
+        assert_eq!(literals(b"[a-z]+"), None);
+        assert_eq!(literals(b"a.*b"), strings(&["a"]));
+    }
+
+    #[test]
+    fn matches_naive() {
+        let text = b"12:30 ERROR disk full\n12:31 WARN net 404\nerror: color colour colr\n";
+        let sa = SaIsBuilder::new().build(text);
+        let patterns: [&[u8]; 10] = [
+            b"(ERROR|WARN) [0-9]+",
+            b"(ERROR|WARN) [a-z]+",
+            b"[0-9]{2}:[0-9]{2}",
+            b"colou?r",
+            b"[a-z]+",
+            b"\\d+",
+            b"e.*r",
+            b"o(l|u)+",
+            b"x?",
+            b"net|disk|full",
+        ];
+        for pattern in patterns {
+            let regex = Regex::parse(pattern).unwrap();
+            assert_eq!(regex_search(text, sa.array(), &regex), regex.find_all(text));
+        }
+
+        let regex = Regex::parse(b"(ERROR|WARN) [a-z]+").unwrap();
+        let matches = regex_search(text, sa.array(), &regex);
+        assert_eq!(spans(text, &matches), [&b"ERROR disk"[..], b"WARN net"]);
+        let regex = Regex::parse(b"colou?r").unwrap();
+        let matches = regex_search(text, sa.array(), &regex);
+        assert_eq!(spans(text, &matches), [&b"color"[..], b"colour"]);
+    }
+}
@@ -0,0 +1,212 @@
+// Computes the runs (maximal repetitions) of a text.
+//
+// A run is a substring text[start..start + length] with smallest period p, where
+// length >= 2p, and which cannot be extended by one char to the left or right with the same
+// period. e.g. "aabaabaa" contains the runs "aa" (x3) and "aabaabaa" (period 3).
+//
+// This follows the Runs theorem: Bannai, Tomohiro et al. (2017). The "Runs" Theorem.
+// Each run of period p contains a Lyndon word of length p which is the longest Lyndon word
+// starting at its position, under one of the two alphabet orders. So it is enough to check
+// each entry of the two Lyndon arrays as a candidate period.
+
+use super::lcp::LceIndex;
+use super::suffix_array::{inverse_suffix_array, SuffixArrayBuilder, TextSize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Run {
+    pub start: TextSize,
+    pub period: TextSize,
+    pub length: TextSize,
+}
+
+// Computes the Lyndon array from the inverse suffix array.
+// lyndon[i] = length of the longest Lyndon word starting at i. That word ends just before the
+// next suffix that is smaller than suffix i.
+// e.g. the Lyndon array of "banana" is [1, 2, 1, 2, 1, 1].
+pub fn lyndon_array(inverse_sa: &[TextSize]) -> Vec<TextSize> {
+    let len = inverse_sa.len();
+    let mut lyndon: Vec<TextSize> = vec![0; len];
+
+    // A stack of positions with increasing ranks, waiting for their next smaller rank.
+    let mut stack: Vec<usize> = Vec::new();
+    for (pos, &rank) in inverse_sa.iter().enumerate() {
+        while let Some(&top) = stack.last() {
+            if inverse_sa[top] < rank {
+                break;
+            }
+            lyndon[top] = (pos - top) as TextSize;
+            stack.pop();
+        }
+        stack.push(pos);
+    }
+    for top in stack {
+        lyndon[top] = (len - top) as TextSize;
+    }
+    lyndon
+}
+
+// Computes all the runs of a text, sorted by (start, period).
+pub fn compute_runs(sa_builder: &dyn SuffixArrayBuilder, text: &[u8]) -> Vec<Run> {
+    let len = text.len();
+    if len < 2 {
+        return Vec::new();
+    }
+
+    // Forward extensions are LCE queries on the text, backward extensions are LCE queries on
+    // the reversed text.
+    let forward_sa = sa_builder.build(text);
+    let forward = LceIndex::new(text, forward_sa.array());
+    let reversed_text: Vec<u8> = text.iter().rev().copied().collect();
+    let reversed_sa = sa_builder.build(&reversed_text);
+    let backward = LceIndex::new(&reversed_text, reversed_sa.array());
+
+    // The Lyndon array under the inverted alphabet order.
+    let inverted_text: Vec<u8> = text.iter().map(|&ch| 255 - ch).collect();
+    let inverted_sa = sa_builder.build(&inverted_text);
+
+    let lyndon_arrays = [
+        lyndon_array(&inverse_suffix_array(forward_sa.array())),
+        lyndon_array(&inverse_suffix_array(inverted_sa.array())),
+    ];
+
+    let mut runs: Vec<Run> = Vec::new();
+    for lyndon in lyndon_arrays.iter() {
+        for (pos, &period) in lyndon.iter().enumerate().skip(1) {
+            let period = period as usize;
+
+            // Extend text[pos..pos + period] to the right and to the left with the same period.
+            let right = forward.lce(pos as TextSize, (pos + period) as TextSize) as usize;
+            let left =
+                backward.lce((len - pos) as TextSize, (len - pos - period) as TextSize) as usize;
+            let length = left + period + right;
+            if length >= 2 * period {
+                runs.push(Run {
+                    start: (pos - left) as TextSize,
+                    period: period as TextSize,
+                    length: length as TextSize,
+                });
+            }
+        }
+    }
+    runs.sort();
+    runs.dedup();
+    runs
+}
+
+// Finds all squares (tandem repeats) ww where |w| <= max_period, by scanning the text once for
+// each period. Each square is returned as a Run of length 2 * period, sorted by (start, period).
+// The period of a square is not necessarily its smallest period, e.g. "aaaa" is a square of
+// period 2.
+pub fn tandem_repeats(text: &[u8], max_period: TextSize) -> Vec<Run> {
+    let len = text.len();
+    let mut squares: Vec<Run> = Vec::new();
+    for period in 1..=(max_period as usize).min(len / 2) {
+        // Number of consecutive positions i, ending here, with text[i] == text[i + period].
+        let mut matches = 0;
+        for i in 0..len - period {
+            if text[i] == text[i + period] {
+                matches += 1;
+            } else {
+                matches = 0;
+            }
+            if matches >= period {
+                squares.push(Run {
+                    start: (i + 1 - period) as TextSize,
+                    period: period as TextSize,
+                    length: (2 * period) as TextSize,
+                });
+            }
+        }
+    }
+    squares.sort();
+    squares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+
+    // Naively computes the runs by checking every period.
+    fn naive_runs(text: &[u8]) -> Vec<Run> {
+        let smallest_period = |s: &[u8]| (1..=s.len()).find(|&p| s[p..] == s[..s.len() - p]);
+        let len = text.len();
+        let mut runs: Vec<Run> = Vec::new();
+        for period in 1..=len / 2 {
+            let mut start = 0;
+            while start + period < len {
+                let mut end = start + period;
+                while end < len && text[end] == text[end - period] {
+                    end += 1;
+                }
+                if end - start >= 2 * period && smallest_period(&text[start..end]) == Some(period) {
+                    runs.push(Run {
+                        start: start as TextSize,
+                        period: period as TextSize,
+                        length: (end - start) as TextSize,
+                    });
+                }
+                start = end - period + 1;
+            }
+        }
+        runs.sort();
+        runs
+    }
+
+    #[test]
+    fn lyndon_banana() {
+        let sa = SaIsBuilder::new().build(b"banana");
+        assert_eq!(
+            lyndon_array(&inverse_suffix_array(sa.array())),
+            [1, 2, 1, 2, 1, 1]
+        );
+    }
+
+    #[test]
+    fn runs_match_naive() {
+        let builder = SaIsBuilder::new();
+        let mut test_strings: Vec<Vec<u8>> = [
+            "a",
+            "aa",
+            "aabaabaa",
+            "abaababaabaababaababa",
+            "mississippi",
+            "cabbage abc food abc vegetables",
+        ]
+        .iter()
+        .map(|s| s.as_bytes().to_vec())
+        .collect();
+
+        // Random strings over small alphabets have many runs.
+        let mut rand: usize = 1;
+        for n in 0..200 {
+            let text: Vec<u8> = (0..20 + n % 40)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % (2 + n % 3)) as u8)
+                .collect();
+            test_strings.push(text);
+        }
+
+        for text in test_strings {
+            assert_eq!(compute_runs(&builder, &text), naive_runs(&text));
+
+            // Each square of period p is in a run whose period divides p.
+            let mut squares: Vec<Run> = Vec::new();
+            for run in compute_runs(&builder, &text) {
+                let mut period = run.period;
+                while 2 * period <= run.length && period <= 5 {
+                    for start in run.start..=run.start + run.length - 2 * period {
+                        squares.push(Run {
+                            start,
+                            period,
+                            length: 2 * period,
+                        });
+                    }
+                    period += run.period;
+                }
+            }
+            squares.sort();
+            assert_eq!(tandem_repeats(&text, 5), squares);
+        }
+    }
+}
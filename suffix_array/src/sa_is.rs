@@ -6,11 +6,12 @@ use super::suffix_array::{SuffixArray, SuffixArrayBuilder, TextSize};
 
 // If enabled (> 0), perform more validations and output more debug info.
 #[allow(dead_code)]
-const DEBUG_LEVEL: usize = 1;
+const DEBUG_LEVEL: usize = 0;
 
 // Each suffix string is either a LType or SType.
+#[allow(clippy::enum_variant_names)]
 #[derive(Copy, Clone, PartialEq)]
-enum LSType {
+pub(crate) enum LSType {
     // Suffix at (pos) is LType if it is larger than suffix starting at (pos + 1).
     LType,
 
@@ -23,7 +24,7 @@ enum LSType {
 use LSType::*;
 
 // Represents a sequence of text characters.
-trait Text: std::fmt::Display {
+pub(crate) trait Text: std::fmt::Display {
     // Length of the text.
     fn len(&self) -> TextSize;
 
@@ -31,9 +32,11 @@ trait Text: std::fmt::Display {
     fn char_at(&self, index: TextSize) -> u32;
 
     // Returns a substring.
+    #[allow(dead_code)]
     fn substring(&self, start: TextSize, end: TextSize) -> String;
 
     // Returns a suffix string starting at given index. Used for debugging.
+    #[allow(dead_code)]
     fn suffix_at(&self, index: TextSize) -> String {
         self.substring(index, self.len())
     }
@@ -276,6 +279,7 @@ impl<'a> RecursiveBuilder<'a> {
 
     // Builds the suffix array.
     // Return the sorted suffix positions in sa[0..text.len-1].
+    #[allow(clippy::absurd_extreme_comparisons)]
     fn build(&mut self, sa: &'a mut [TextSize]) {
         if DEBUG_LEVEL >= 1 {
             println!(
@@ -607,9 +611,7 @@ impl<'a> RecursiveBuilder<'a> {
         // Clear out the text_len/2 slots from sa[num_lms..].
         // This will be used for bucket sorting the LMS positions.
         const NULL_NAME: u32 = u32::MAX;
-        for i in num_lms as usize..(num_lms + text_len / 2) as usize {
-            sa[i] = NULL_NAME;
-        }
+        sa[num_lms as usize..(num_lms + text_len / 2) as usize].fill(NULL_NAME);
 
         // Iterate through the sorted LMS strings to assign a name for each unique string.
         let mut last_lms_pos = sa[0];
@@ -678,6 +680,29 @@ impl SaIsBuilder {
     pub fn new() -> SaIsBuilder {
         SaIsBuilder {}
     }
+
+    // Builds the suffix array of a text over an integer alphabet. All chars must be less than
+    // alphabet_size.
+    pub fn build_u32(&self, text: &[u32], alphabet_size: TextSize) -> Vec<TextSize> {
+        assert!(text.iter().all(|&ch| ch < alphabet_size));
+        self.build_text(&U32Text { text }, alphabet_size)
+    }
+
+    // Builds the suffix array of any Text, whose chars are less than alphabet_size.
+    pub(crate) fn build_text(&self, text: &dyn Text, alphabet_size: TextSize) -> Vec<TextSize> {
+        assert!(text.len() < TextSize::MAX - 1);
+        assert!(text.len() > 0);
+        let mut sa: Vec<TextSize> = vec![0; text.len() as usize];
+        let mut sa_builder = RecursiveBuilder::new(text, alphabet_size);
+        sa_builder.build(&mut sa);
+        sa
+    }
+}
+
+impl Default for SaIsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 struct SaIsSuffixArray {
@@ -695,6 +720,7 @@ impl SuffixArray for SaIsSuffixArray {
 }
 
 impl SuffixArrayBuilder for SaIsBuilder {
+    #[allow(clippy::absurd_extreme_comparisons)]
     fn build(&self, text: &[u8]) -> Box<dyn SuffixArray> {
         // Check that the text size is supported.
         assert!(text.len() < (TextSize::MAX - 1) as usize);
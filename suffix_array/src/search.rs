@@ -0,0 +1,343 @@
+// Exact pattern search over a suffix array.
+//
+// Searches return ranges of suffix array rows (ranks). All suffixes in a range start with the
+// pattern, and their positions in the text are sa[range].
+
+use std::ops::Range;
+
+use super::suffix_array::TextSize;
+
+// Narrows a range of rows whose suffixes share a common prefix of length depth, to the rows
+// whose suffixes have ch right after that prefix. The text may be of bytes, or of other symbols
+// such as token IDs.
+pub fn narrow_range<T: Copy + Ord>(
+    text: &[T],
+    sa: &[TextSize],
+    range: Range<usize>,
+    depth: usize,
+    ch: T,
+) -> Range<usize> {
+    // Within the range, the suffixes are sorted by their char at depth. Suffixes that end
+    // before depth come first.
+    let rows = &sa[range.clone()];
+    let char_at = |pos: TextSize| text.get(pos as usize + depth).copied();
+    let start = rows.partition_point(|&pos| char_at(pos) < Some(ch));
+    let end = start + rows[start..].partition_point(|&pos| char_at(pos) == Some(ch));
+    range.start + start..range.start + end
+}
+
+// Splits a range of rows whose suffixes share a common prefix of length depth, by the char
+// right after that prefix. Returns the (char, range) pairs in ascending char order. Suffixes
+// that end at depth are not in any of the ranges.
+pub fn child_ranges(
+    text: &[u8],
+    sa: &[TextSize],
+    range: Range<usize>,
+    depth: usize,
+) -> Vec<(u8, Range<usize>)> {
+    let mut children: Vec<(u8, Range<usize>)> = Vec::new();
+    let mut start = range.start;
+    if start < range.end && sa[start] as usize + depth == text.len() {
+        start += 1;
+    }
+    while start < range.end {
+        let ch = text[sa[start] as usize + depth];
+        let child = narrow_range(text, sa, start..range.end, depth, ch);
+        start = child.end;
+        children.push((ch, child));
+    }
+    children
+}
+
+// Returns the range of rows whose suffixes start with the pattern.
+// The range is empty if the pattern does not occur.
+pub fn find_range(text: &[u8], sa: &[TextSize], pattern: &[u8]) -> Range<usize> {
+    let mut range = 0..sa.len();
+    for (depth, &ch) in pattern.iter().enumerate() {
+        if range.is_empty() {
+            break;
+        }
+        range = narrow_range(text, sa, range, depth, ch);
+    }
+    range
+}
+
+// Returns the range of rows for each of the patterns, in the order of the patterns.
+//
+// The patterns are sorted and walked down the suffix array together, as in a trie: patterns
+// sharing a prefix narrow the range for that prefix only once, and sibling ranges are searched
+// left to right, each one after the previous.
+pub fn find_ranges(text: &[u8], sa: &[TextSize], patterns: &[&[u8]]) -> Vec<Range<usize>> {
+    let mut order: Vec<usize> = (0..patterns.len()).collect();
+    order.sort_unstable_by_key(|&i| patterns[i]);
+    let mut ranges: Vec<Range<usize>> = vec![0..0; patterns.len()];
+    find_sorted_ranges(text, sa, patterns, &order, 0..sa.len(), 0, &mut ranges);
+    ranges
+}
+
+// Finds the ranges of the sorted patterns in order, which share a prefix of length depth, and
+// whose suffixes start with that prefix at the rows in range.
+fn find_sorted_ranges(
+    text: &[u8],
+    sa: &[TextSize],
+    patterns: &[&[u8]],
+    mut order: &[usize],
+    range: Range<usize>,
+    depth: usize,
+    ranges: &mut [Range<usize>],
+) {
+    // Patterns of length depth come first, and match the whole range.
+    while let Some(&i) = order.first().filter(|&&i| patterns[i].len() == depth) {
+        ranges[i] = range.clone();
+        order = &order[1..];
+    }
+
+    let mut start = range.start;
+    while let Some(&first) = order.first() {
+        let ch = patterns[first][depth];
+        let count = order.partition_point(|&i| patterns[i][depth] == ch);
+        let child = narrow_range(text, sa, start..range.end, depth, ch);
+        if child.is_empty() {
+            for &i in &order[..count] {
+                ranges[i] = child.clone();
+            }
+        } else {
+            find_sorted_ranges(
+                text,
+                sa,
+                patterns,
+                &order[..count],
+                child.clone(),
+                depth + 1,
+                ranges,
+            );
+        }
+        start = child.end;
+        order = &order[count..];
+    }
+}
+
+// Returns the positions of all occurrences of the pattern, in text order.
+pub fn locate(text: &[u8], sa: &[TextSize], pattern: &[u8]) -> Vec<TextSize> {
+    let mut positions: Vec<TextSize> = sa[find_range(text, sa, pattern)].to_vec();
+    positions.sort_unstable();
+    positions
+}
+
+// Returns the range of rows whose suffixes are lexicographically in [low, high).
+pub fn lexicographic_range(text: &[u8], sa: &[TextSize], low: &[u8], high: &[u8]) -> Range<usize> {
+    let start = sa.partition_point(|&pos| &text[pos as usize..] < low);
+    let end = sa.partition_point(|&pos| &text[pos as usize..] < high);
+    start..end.max(start)
+}
+
+// Returns the ranges of rows whose suffixes start with the prefix and are at least min_len long,
+// in row order. Shorter suffixes starting with the prefix split the range of the prefix.
+pub fn prefix_ranges(
+    text: &[u8],
+    sa: &[TextSize],
+    prefix: &[u8],
+    min_len: usize,
+) -> Vec<Range<usize>> {
+    let range = find_range(text, sa, prefix);
+    let rows = &sa[range.clone()];
+    let mut short_rows: Vec<usize> = Vec::new();
+    for pos in (text.len() + 1).saturating_sub(min_len)..text.len() {
+        let suffix = &text[pos..];
+        if suffix.starts_with(prefix) {
+            short_rows.push(range.start + rows.partition_point(|&p| &text[p as usize..] < suffix));
+        }
+    }
+    short_rows.sort_unstable();
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut start = range.start;
+    for row in short_rows.into_iter().chain(std::iter::once(range.end)) {
+        if start < row {
+            ranges.push(start..row);
+        }
+        start = row + 1;
+    }
+    ranges
+}
+
+// Iterates through the distinct continuations of a prefix, in lexicographic order.
+// A continuation is the text of up to len bytes right after an occurrence of the prefix. It is
+// shorter than len only where the text ends.
+pub struct Continuations<'a> {
+    text: &'a [u8],
+    sa: &'a [TextSize],
+    depth: usize,
+    len: usize,
+
+    // The remaining rows whose suffixes start with the prefix.
+    range: Range<usize>,
+}
+
+impl<'a> Iterator for Continuations<'a> {
+    // The continuation, and the number of its occurrences after the prefix.
+    type Item = (&'a [u8], usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.range.is_empty() {
+            return None;
+        }
+        let start = self.sa[self.range.start] as usize + self.depth;
+        let end = (start + self.len).min(self.text.len());
+        let continuation = &self.text[start..end];
+
+        // A continuation cut short by the end of the text occurs only once.
+        let mut group = self.range.start..self.range.start + 1;
+        if continuation.len() == self.len {
+            group = self.range.clone();
+            for (i, &ch) in continuation.iter().enumerate() {
+                group = narrow_range(self.text, self.sa, group, self.depth + i, ch);
+            }
+        }
+        self.range.start = group.end;
+        Some((continuation, group.len()))
+    }
+}
+
+// Returns an iterator of the distinct continuations of up to len bytes after the prefix, with
+// their frequencies.
+// e.g. in "banana", the continuations of "a" with len = 2 are ("", 1) and ("na", 2).
+pub fn continuations<'a>(
+    text: &'a [u8],
+    sa: &'a [TextSize],
+    prefix: &[u8],
+    len: usize,
+) -> Continuations<'a> {
+    Continuations {
+        text,
+        sa,
+        depth: prefix.len(),
+        len,
+        range: find_range(text, sa, prefix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    #[test]
+    fn banana() {
+        let text = b"banana";
+        let suffix_array = SaIsBuilder::new().build(text);
+        let sa = suffix_array.array();
+        assert_eq!(find_range(text, sa, b""), 0..6);
+        assert_eq!(find_range(text, sa, b"a"), 0..3);
+        assert_eq!(find_range(text, sa, b"ana"), 1..3);
+        assert_eq!(find_range(text, sa, b"nab").len(), 0);
+        assert_eq!(locate(text, sa, b"na"), [2, 4]);
+        assert_eq!(suffix_array.locate(text, b"banana"), [0]);
+        assert!(suffix_array.locate(text, b"bananas").is_empty());
+        assert_eq!(child_ranges(text, sa, 0..3, 1), [(b'n', 1..3)]);
+        assert_eq!(
+            child_ranges(text, sa, 0..6, 0),
+            [(b'a', 0..3), (b'b', 3..4), (b'n', 4..6)]
+        );
+    }
+
+    #[test]
+    fn batch() {
+        let mut rand: usize = 31;
+        let mut random_text = |len: usize, alphabet: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % alphabet) as u8)
+                .collect()
+        };
+        for n in 0..20 {
+            let text = random_text(100 + n * 10, 2 + n % 3);
+            let sa = SaIsBuilder::new().build(&text);
+            let mut patterns: Vec<Vec<u8>> =
+                (0..50).map(|i| random_text(i % 7, 2 + n % 4)).collect();
+            patterns.push(patterns[3].clone());
+            let patterns: Vec<&[u8]> = patterns.iter().map(|p| p.as_slice()).collect();
+
+            let ranges = sa.find_ranges(&text, &patterns);
+            for (pattern, range) in patterns.iter().zip(ranges) {
+                let expected = find_range(&text, sa.array(), pattern);
+                assert_eq!(range.len(), expected.len());
+                if !range.is_empty() {
+                    assert_eq!(range, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)]
+    fn range_queries() {
+        let text = b"banana";
+        let sa = SaIsBuilder::new().build(text);
+        // Rows: a, ana, anana, banana, na, nana.
+        assert_eq!(sa.lexicographic_range(text, b"an", b"b"), 1..3);
+        assert_eq!(sa.lexicographic_range(text, b"a", b"nb"), 0..6);
+        assert_eq!(sa.lexicographic_range(text, b"z", b"a"), 6..6);
+
+        assert_eq!(sa.prefix_ranges(text, b"a", 0), [0..3]);
+        assert_eq!(sa.prefix_ranges(text, b"a", 3), [1..3]);
+        assert_eq!(sa.prefix_ranges(text, b"an", 4), [2..3]);
+        assert_eq!(sa.prefix_ranges(text, b"", 4), [2..4, 5..6]);
+        assert!(sa.prefix_ranges(text, b"x", 1).is_empty());
+
+        let continuations: Vec<(&[u8], usize)> = sa.continuations(text, b"na", 1).collect();
+        assert_eq!(continuations, [(&b""[..], 1), (b"n", 1)]);
+        let continuations: Vec<(&[u8], usize)> = sa.continuations(text, b"an", 1).collect();
+        assert_eq!(continuations, [(&b"a"[..], 2)]);
+        let continuations: Vec<(&[u8], usize)> = sa.continuations(text, b"", 2).collect();
+        assert_eq!(
+            continuations,
+            [(&b"a"[..], 1), (b"an", 2), (b"ba", 1), (b"na", 2)]
+        );
+        let continuations: Vec<(&[u8], usize)> = sa.continuations(text, b"a", 2).collect();
+        assert_eq!(continuations, [(&b""[..], 1), (b"na", 2)]);
+        assert_eq!(sa.continuations(text, b"x", 2).count(), 0);
+    }
+
+    #[test]
+    fn range_queries_match_naive() {
+        let text = b"abracadabra abracadabra cadabra";
+        let sa = SaIsBuilder::new().build(text);
+        let suffixes: Vec<&[u8]> = sa.iter().map(|pos| &text[pos as usize..]).collect();
+        let patterns: [&[u8]; 6] = [b"", b"a", b"abra", b"cad", b"ra", b"x"];
+        for low in patterns {
+            for high in patterns {
+                let expected: Vec<usize> = (0..suffixes.len())
+                    .filter(|&i| suffixes[i] >= low && suffixes[i] < high)
+                    .collect();
+                let range = sa.lexicographic_range(text, low, high);
+                assert_eq!(range.collect::<Vec<usize>>(), expected);
+            }
+            for min_len in [0, 3, 5, 12, 40] {
+                let expected: Vec<usize> = (0..suffixes.len())
+                    .filter(|&i| suffixes[i].starts_with(low) && suffixes[i].len() >= min_len)
+                    .collect();
+                let rows: Vec<usize> = sa
+                    .prefix_ranges(text, low, min_len)
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                assert_eq!(rows, expected);
+            }
+            for len in 0..4 {
+                let mut expected: Vec<(&[u8], usize)> = Vec::new();
+                for suffix in suffixes.iter().filter(|s| s.starts_with(low)) {
+                    let rest = &suffix[low.len()..];
+                    let continuation = &rest[..len.min(rest.len())];
+                    match expected.last_mut() {
+                        Some((last, count)) if *last == continuation => *count += 1,
+                        _ => expected.push((continuation, 1)),
+                    }
+                }
+                let continuations: Vec<(&[u8], usize)> = sa.continuations(text, low, len).collect();
+                assert_eq!(continuations, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+// Sparse suffix arrays, over a selected set of suffixes.
+//
+// Only the suffixes that start at the selected positions are sorted, so the working memory is
+// O(m) words for m selected positions, rather than n words for the whole text.
+//
+// When every k-th position is selected, the text is read as a sequence of k-byte blocks. The
+// distinct blocks are sorted and ranked, and the suffix array of the sequence of block ranks, built
+// with SA-IS, is the sparse suffix array. This takes O(m log m) block comparisons and O(m) time
+// for SA-IS, however repetitive the text is.
+//
+// Other positions are sorted with a multikey quicksort, which compares the suffixes one byte at a
+// time. It takes O(m log m) time plus the total length of the prefixes that distinguish the
+// suffixes, which is large when selected suffixes share long prefixes, as in repetitive texts.
+//
+// The usual searches find the occurrences that start at selected positions. When every k-th
+// position is selected, every occurrence of a pattern of length >= k has a selected position
+// within its first k bytes, so all the occurrences can be found by searching for the k suffixes
+// of the pattern, and checking the bytes before the selected positions.
+
+use super::sa_is::SaIsBuilder;
+use super::search::find_range;
+use super::suffix_array::{SuffixArray, TextSize};
+
+// Builds sparse suffix arrays.
+pub struct SparseBuilder {}
+
+impl SparseBuilder {
+    pub fn new() -> SparseBuilder {
+        SparseBuilder {}
+    }
+
+    // Sorts the suffixes that start at the given positions. Duplicates are removed.
+    pub fn build_positions(&self, text: &[u8], positions: &[TextSize]) -> SparseSuffixArray {
+        self.sort(text, positions.to_vec())
+    }
+
+    // Sorts the suffixes whose start positions satisfy the predicate.
+    pub fn build_predicate(
+        &self,
+        text: &[u8],
+        predicate: &dyn Fn(usize) -> bool,
+    ) -> SparseSuffixArray {
+        let positions: Vec<TextSize> = (0..text.len())
+            .filter(|&pos| predicate(pos))
+            .map(|pos| pos as TextSize)
+            .collect();
+        self.sort(text, positions)
+    }
+
+    // Sorts the suffixes that start at multiples of step.
+    pub fn build_every(&self, text: &[u8], step: usize) -> SparseSuffixArray {
+        assert!(step > 0);
+        let blocks: Vec<&[u8]> = text.chunks(step).collect();
+        if blocks.is_empty() {
+            return SparseSuffixArray {
+                sa: Vec::new(),
+                step: Some(step),
+            };
+        }
+
+        // A shorter last block is ranked before the blocks that it is a prefix of, as its suffix
+        // ends there.
+        let mut distinct: Vec<&[u8]> = blocks.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let ranks: Vec<u32> = blocks
+            .iter()
+            .map(|block| distinct.binary_search(block).unwrap() as u32)
+            .collect();
+
+        let mut sa = SaIsBuilder::new().build_u32(&ranks, distinct.len() as TextSize);
+        for pos in sa.iter_mut() {
+            *pos *= step as TextSize;
+        }
+        SparseSuffixArray {
+            sa,
+            step: Some(step),
+        }
+    }
+
+    fn sort(&self, text: &[u8], mut positions: Vec<TextSize>) -> SparseSuffixArray {
+        positions.sort_unstable();
+        positions.dedup();
+        assert!(positions
+            .last()
+            .is_none_or(|&pos| (pos as usize) < text.len()));
+        multikey_sort(text, &mut positions);
+        SparseSuffixArray {
+            sa: positions,
+            step: None,
+        }
+    }
+}
+
+// Sorts distinct suffixes with a multikey quicksort (Bentley-Sedgewick). Each range of suffixes
+// that share a prefix of length depth is partitioned by the byte at depth, and the suffixes with
+// the pivot byte are then sorted at depth + 1. The ranges are kept on an explicit stack, since
+// the depth can be as large as the text.
+fn multikey_sort(text: &[u8], positions: &mut [TextSize]) {
+    // Suffixes that end at depth sort first.
+    let key =
+        |pos: TextSize, depth: usize| text.get(pos as usize + depth).map_or(-1, |&ch| ch as i32);
+    let mut stack: Vec<(usize, usize, usize)> = vec![(0, positions.len(), 0)];
+    while let Some((start, end, depth)) = stack.pop() {
+        if end - start < 2 {
+            continue;
+        }
+        let pivot = key(positions[start + (end - start) / 2], depth);
+        let (mut less, mut i, mut greater) = (start, start, end);
+        while i < greater {
+            let ch = key(positions[i], depth);
+            if ch < pivot {
+                positions.swap(less, i);
+                less += 1;
+                i += 1;
+            } else if ch > pivot {
+                greater -= 1;
+                positions.swap(i, greater);
+            } else {
+                i += 1;
+            }
+        }
+        stack.push((start, less, depth));
+        stack.push((greater, end, depth));
+        // At most one suffix ends at depth, as the positions are distinct.
+        if pivot >= 0 {
+            stack.push((less, greater, depth + 1));
+        }
+    }
+}
+
+impl Default for SparseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct SparseSuffixArray {
+    sa: Vec<TextSize>,
+
+    // Set if the suffixes start at every step-th position.
+    step: Option<usize>,
+}
+
+impl SparseSuffixArray {
+    // Returns the positions of all the occurrences of the pattern, in text order. This needs every
+    // k-th position to be selected; otherwise only the occurrences at selected positions are
+    // found, as with locate(). Patterns shorter than k may not contain a selected position, so
+    // they are found by scanning the text, in O(n) time.
+    pub fn locate_all(&self, text: &[u8], pattern: &[u8]) -> Vec<TextSize> {
+        let step = match self.step {
+            Some(step) => step,
+            None => return self.locate(text, pattern),
+        };
+        if pattern.len() < step {
+            return (0..text.len())
+                .filter(|&pos| text[pos..].starts_with(pattern))
+                .map(|pos| pos as TextSize)
+                .collect();
+        }
+
+        // An occurrence at pos is found from the selected position pos + skip.
+        let mut positions: Vec<TextSize> = Vec::new();
+        for skip in 0..step {
+            for &pos in &self.sa[find_range(text, &self.sa, &pattern[skip..])] {
+                let pos = pos as usize;
+                if pos >= skip && text[pos - skip..pos] == pattern[..skip] {
+                    positions.push((pos - skip) as TextSize);
+                }
+            }
+        }
+        positions.sort_unstable();
+        positions
+    }
+}
+
+impl SuffixArray for SparseSuffixArray {
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = TextSize> + 'a> {
+        Box::new(self.sa.iter().copied())
+    }
+
+    fn array(&self) -> &[TextSize] {
+        &self.sa
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    #[test]
+    fn word_starts() {
+        let text = b"the cat sat on the mat";
+        let is_word_start = |pos: usize| pos == 0 || text[pos - 1] == b' ';
+        let sa = SparseBuilder::new().build_predicate(text, &is_word_start);
+        let words: Vec<&[u8]> = sa.iter().map(|pos| &text[pos as usize..]).collect();
+        assert_eq!(
+            words,
+            [
+                &b"cat sat on the mat"[..],
+                b"mat",
+                b"on the mat",
+                b"sat on the mat",
+                b"the cat sat on the mat",
+                b"the mat"
+            ]
+        );
+        assert_eq!(sa.locate(text, b"the"), [0, 15]);
+        // "at" does not start a word.
+        assert!(sa.locate(text, b"at").is_empty());
+        assert_eq!(sa.locate_all(text, b"at"), sa.locate(text, b"at"));
+
+        let sa = SparseBuilder::new().build_positions(text, &[19, 5, 19, 1]);
+        assert_eq!(sa.array(), [5, 1, 19]);
+        assert!(SparseBuilder::new().build_every(b"", 3).array().is_empty());
+        assert!(SparseBuilder::new()
+            .build_positions(b"", &[])
+            .array()
+            .is_empty());
+    }
+
+    #[test]
+    fn repetitive_text() {
+        // Long shared prefixes, and a shorter last block.
+        let text: Vec<u8> = (0..5000)
+            .map(|i| if i == 4000 { b'b' } else { b'a' })
+            .collect();
+        let full = SaIsBuilder::new().build(&text);
+        for step in [1, 3, 7, 64] {
+            let expected: Vec<TextSize> = full
+                .iter()
+                .filter(|&pos| (pos as usize).is_multiple_of(step))
+                .collect();
+            let positions: Vec<TextSize> = (0..text.len() as TextSize).step_by(step).collect();
+            assert_eq!(
+                SparseBuilder::new().build_every(&text, step).array(),
+                expected
+            );
+            assert_eq!(
+                SparseBuilder::new()
+                    .build_positions(&text, &positions)
+                    .array(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn matches_full_suffix_array() {
+        let mut rand: usize = 31;
+        for n in 1..40 {
+            let text: Vec<u8> = (0..n * 11)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % (2 + n % 3)) as u8)
+                .collect();
+            let full = SaIsBuilder::new().build(&text);
+            for step in 1..6 {
+                let sa = SparseBuilder::new().build_every(&text, step);
+                let expected: Vec<TextSize> = full
+                    .iter()
+                    .filter(|&pos| (pos as usize).is_multiple_of(step))
+                    .collect();
+                assert_eq!(sa.array(), expected);
+                let is_selected = |pos: usize| pos % (step + 1) != 1;
+                let expected: Vec<TextSize> = full
+                    .iter()
+                    .filter(|&pos| is_selected(pos as usize))
+                    .collect();
+                assert_eq!(
+                    SparseBuilder::new()
+                        .build_predicate(&text, &is_selected)
+                        .array(),
+                    expected
+                );
+
+                for len in 0..9 {
+                    let start =
+                        testing::next_random(&mut rand) % (text.len() - len.min(text.len()) + 1);
+                    let pattern = &text[start..(start + len).min(text.len())];
+                    assert_eq!(
+                        sa.locate_all(&text, pattern),
+                        full.locate(&text, pattern),
+                        "step {} pattern {:?}",
+                        step,
+                        pattern
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// Shortest unique substrings and minimal absent words, computed from the suffix array and the
+// LCP array.
+//
+// A substring is unique if it occurs exactly once in the text.
+// e.g. in "banana", "b" is unique, and "ban" is the shortest unique substring covering pos 2.
+//
+// A minimal absent word does not occur in the text, but all its proper substrings do.
+// e.g. "aa" and "bn" are minimal absent words of "banana". The alphabet is the set of chars that
+// occur in the text, so minimal absent words have at least 2 chars.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::Range;
+
+use super::lcp::{lcp_array_with_inverse, lcp_intervals};
+use super::suffix_array::{inverse_suffix_array, TextSize};
+
+// Computes the length of the shortest unique substring starting at each pos, i.e. the shortest
+// prefix of each suffix that is not a prefix of any other suffix.
+// unique_prefix_len[pos] = None if every prefix of suffix pos occurs more than once.
+pub fn unique_prefix_lengths(text: &[u8], sa: &[TextSize]) -> Vec<Option<TextSize>> {
+    let len = text.len();
+    let inverse_sa = inverse_suffix_array(sa);
+    let lcp = lcp_array_with_inverse(text, sa, &inverse_sa);
+    (0..len)
+        .map(|pos| {
+            // The prefix must be longer than the common prefix with both neighbours.
+            let rank = inverse_sa[pos] as usize;
+            let next_lcp = if rank + 1 < len { lcp[rank + 1] } else { 0 };
+            let unique_len = lcp[rank].max(next_lcp) + 1;
+            if pos + unique_len as usize <= len {
+                Some(unique_len)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Computes the shortest unique substring covering each pos, as a range of the text.
+// If there are several, the leftmost one is returned. Every pos is covered, since the whole
+// text is unique.
+pub fn shortest_unique_substrings(text: &[u8], sa: &[TextSize]) -> Vec<Range<usize>> {
+    let len = text.len();
+    let unique_len = unique_prefix_lengths(text, sa);
+
+    // A unique substring stays unique when extended. So the candidates covering pos are
+    // text[start..start + unique_len[start]] if it covers pos, or else text[start..pos + 1].
+
+    // last_start[end] = the largest start whose shortest unique substring ends at or before end.
+    let mut last_start: Vec<Option<usize>> = vec![None; len];
+    for (start, &unique) in unique_len.iter().enumerate() {
+        if let Some(l) = unique {
+            let end = start + l as usize - 1;
+            last_start[end] = Some(start.max(last_start[end].unwrap_or(0)));
+        }
+    }
+
+    // A heap of (len, start) of the unique substrings that started at or before pos.
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+    let mut extended_start: Option<usize> = None;
+    let mut result: Vec<Range<usize>> = Vec::with_capacity(len);
+    for pos in 0..len {
+        if let Some(l) = unique_len[pos] {
+            heap.push(Reverse((l as usize, pos)));
+        }
+        // Remove the substrings that end before pos.
+        while let Some(&Reverse((l, start))) = heap.peek() {
+            if start + l > pos {
+                break;
+            }
+            heap.pop();
+        }
+
+        let mut best: Option<Range<usize>> =
+            heap.peek().map(|&Reverse((l, start))| start..start + l);
+
+        // Extend the nearest unique substring that ended before pos.
+        if let Some(start) = extended_start {
+            let extended = start..pos + 1;
+            if best
+                .as_ref()
+                .is_none_or(|b| (extended.len(), extended.start) < (b.len(), b.start))
+            {
+                best = Some(extended);
+            }
+        }
+        result.push(best.unwrap());
+
+        if let Some(start) = last_start[pos] {
+            extended_start = Some(start.max(extended_start.unwrap_or(0)));
+        }
+    }
+    result
+}
+
+// Computes the minimal absent words of length <= max_len, sorted lexicographically.
+//
+// A word (a + u + b) is a minimal absent word if (a + u) and (u + b) occur but (a + u + b) does
+// not. u must be followed by at least two different chars (or the end of text), so it is the
+// common prefix of an LCP interval. The chars before each suffix in the interval are the BWT
+// chars of the interval, and they give the candidates for a.
+pub fn minimal_absent_words(text: &[u8], sa: &[TextSize], max_len: usize) -> Vec<Vec<u8>> {
+    let len = text.len();
+    let inverse_sa = inverse_suffix_array(sa);
+    let lcp = lcp_array_with_inverse(text, sa, &inverse_sa);
+
+    let mut words: Vec<Vec<u8>> = Vec::new();
+
+    // The (before, after) pairs that occur, shared by the intervals. Only the pairs that were set
+    // are cleared after each interval.
+    let mut pairs: Vec<bool> = vec![false; 256 * 256];
+    let mut set_pairs: Vec<usize> = Vec::new();
+    for interval in lcp_intervals(&lcp) {
+        let u_len = interval.lcp as usize;
+        if u_len + 2 > max_len {
+            continue;
+        }
+
+        // Chars before and after u.
+        let mut left: [bool; 256] = [false; 256];
+        let mut right: [bool; 256] = [false; 256];
+        for &pos in sa[interval.start..interval.end].iter() {
+            let pos = pos as usize;
+            let before = if pos > 0 { Some(text[pos - 1]) } else { None };
+            let after = if pos + u_len < len {
+                Some(text[pos + u_len])
+            } else {
+                None
+            };
+            if let Some(a) = before {
+                left[a as usize] = true;
+            }
+            if let Some(b) = after {
+                right[b as usize] = true;
+            }
+            if let (Some(a), Some(b)) = (before, after) {
+                let pair = a as usize * 256 + b as usize;
+                if !pairs[pair] {
+                    pairs[pair] = true;
+                    set_pairs.push(pair);
+                }
+            }
+        }
+
+        let u_start = sa[interval.start] as usize;
+        let u = &text[u_start..u_start + u_len];
+        let rights: Vec<usize> = (0..256).filter(|&b| right[b]).collect();
+        for a in (0..256).filter(|&a| left[a]) {
+            for &b in rights.iter() {
+                if !pairs[a * 256 + b] {
+                    let mut word: Vec<u8> = Vec::with_capacity(u_len + 2);
+                    word.push(a as u8);
+                    word.extend_from_slice(u);
+                    word.push(b as u8);
+                    words.push(word);
+                }
+            }
+        }
+        for pair in set_pairs.drain(..) {
+            pairs[pair] = false;
+        }
+    }
+    words.sort();
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    fn count_occurrences(text: &[u8], word: &[u8]) -> usize {
+        if word.len() > text.len() {
+            return 0;
+        }
+        text.windows(word.len()).filter(|&w| w == word).count()
+    }
+
+    #[test]
+    fn banana() {
+        let text = b"banana";
+        let sa = SaIsBuilder::new().build(text);
+        assert_eq!(
+            unique_prefix_lengths(text, sa.array()),
+            [Some(1), Some(4), Some(3), None, None, None]
+        );
+        assert_eq!(
+            shortest_unique_substrings(text, sa.array()),
+            [0..1, 0..2, 0..3, 2..5, 2..5, 2..6]
+        );
+
+        let expected: Vec<&[u8]> = vec![b"aa", b"ab", b"bb", b"bn", b"nb", b"nn"];
+        assert_eq!(minimal_absent_words(text, sa.array(), 3), expected);
+    }
+
+    #[test]
+    fn matches_naive() {
+        let builder = SaIsBuilder::new();
+        let mut rand: usize = 5;
+        for n in 0..50 {
+            let text: Vec<u8> = (0..5 + n % 30)
+                .map(|_| b'a' + (testing::next_random(&mut rand) % (2 + n % 3)) as u8)
+                .collect();
+            let sa = builder.build(&text);
+            let len = text.len();
+
+            // Shortest unique substrings covering each pos.
+            let sus = shortest_unique_substrings(&text, sa.array());
+            for (pos, sus_range) in sus.iter().enumerate() {
+                let best = (1..=len)
+                    .flat_map(|l| (0..=len - l).map(move |start| start..start + l))
+                    .find(|r| r.contains(&pos) && count_occurrences(&text, &text[r.clone()]) == 1)
+                    .unwrap();
+                assert_eq!(*sus_range, best);
+            }
+
+            // Minimal absent words up to length 4.
+            let mut alphabet: Vec<u8> = text.clone();
+            alphabet.sort();
+            alphabet.dedup();
+            let mut words: Vec<Vec<u8>> = vec![Vec::new()];
+            let mut expected: Vec<Vec<u8>> = Vec::new();
+            for _ in 0..4 {
+                words = words
+                    .iter()
+                    .flat_map(|w| {
+                        alphabet
+                            .iter()
+                            .map(move |&ch| [w.as_slice(), &[ch]].concat())
+                    })
+                    .collect();
+                for w in words.iter() {
+                    if w.len() >= 2
+                        && count_occurrences(&text, w) == 0
+                        && count_occurrences(&text, &w[1..]) > 0
+                        && count_occurrences(&text, &w[..w.len() - 1]) > 0
+                    {
+                        expected.push(w.clone());
+                    }
+                }
+            }
+            expected.sort();
+            assert_eq!(minimal_absent_words(&text, sa.array(), 4), expected);
+        }
+    }
+}
@@ -1,5 +1,9 @@
 // Builds a SuffixArray.
 
+use std::ops::Range;
+
+use super::search;
+
 #[allow(dead_code)]
 const DEBUG_LEVEL: usize = 1;
 
@@ -19,16 +23,60 @@ pub trait SuffixArray {
 
     // Returns an array.
     fn array(&self) -> &[TextSize];
+
+    // Returns the range of ranks of the suffixes of text that start with the pattern.
+    fn find_range(&self, text: &[u8], pattern: &[u8]) -> Range<usize> {
+        search::find_range(text, self.array(), pattern)
+    }
+
+    // Returns the range of ranks for each of the patterns, in the order of the patterns.
+    fn find_ranges(&self, text: &[u8], patterns: &[&[u8]]) -> Vec<Range<usize>> {
+        search::find_ranges(text, self.array(), patterns)
+    }
+
+    // Returns the range of ranks of the suffixes of text that are lexicographically in
+    // [low, high).
+    fn lexicographic_range(&self, text: &[u8], low: &[u8], high: &[u8]) -> Range<usize> {
+        search::lexicographic_range(text, self.array(), low, high)
+    }
+
+    // Returns the ranges of ranks of the suffixes of text that start with the prefix and are at
+    // least min_len long.
+    fn prefix_ranges(&self, text: &[u8], prefix: &[u8], min_len: usize) -> Vec<Range<usize>> {
+        search::prefix_ranges(text, self.array(), prefix, min_len)
+    }
+
+    // Returns an iterator of the distinct continuations of up to len bytes after the prefix in
+    // text, with their frequencies.
+    fn continuations<'a>(
+        &'a self,
+        text: &'a [u8],
+        prefix: &[u8],
+        len: usize,
+    ) -> search::Continuations<'a> {
+        search::continuations(text, self.array(), prefix, len)
+    }
+
+    // Returns the positions of all occurrences of the pattern in text, in text order.
+    fn locate(&self, text: &[u8], pattern: &[u8]) -> Vec<TextSize> {
+        search::locate(text, self.array(), pattern)
+    }
+}
+
+// Computes the inverse suffix array, so that inverse_sa[sa[i]] = i.
+pub fn inverse_suffix_array(sa: &[TextSize]) -> Vec<TextSize> {
+    let mut inverse_sa: Vec<TextSize> = vec![0; sa.len()];
+    for (i, &pos) in sa.iter().enumerate() {
+        inverse_sa[pos as usize] = i as TextSize;
+    }
+    inverse_sa
 }
 
 // Validates a suffix array is correct.
 pub fn validate_suffix_array(text: &[u8], sa: &[TextSize]) {
     // Make an inverse suffix array so that inverse_sa[sa[i]] = i.
     let len = text.len() as TextSize;
-    let mut inverse_sa: Vec<TextSize> = vec![0; len as usize];
-    for i in 0..len {
-        inverse_sa[sa[i as usize] as usize] = i;
-    }
+    let inverse_sa = inverse_suffix_array(sa);
 
     // Start with sa[0].
     let mut prev_ch = text[sa[0] as usize];
@@ -94,7 +142,7 @@ mod tests {
             if DEBUG_LEVEL >= 1 {
                 println!(
                     "Test string: {}",
-                    String::from_utf8_lossy(&test_bytes).to_string()
+                    String::from_utf8_lossy(test_bytes)
                 );
             }
 
@@ -106,18 +154,12 @@ mod tests {
                 &mut sa_naive.iter().copied()
             ));
 
-            validate_suffix_array(&test_bytes, suffix_array.array());
+            validate_suffix_array(test_bytes, suffix_array.array());
         }
     }
 
     // Test building suffix array from random strings.
     fn test_random_strings(sa_builder: &dyn SuffixArrayBuilder) {
-        // A primitive random function.
-        fn next_random(num: &mut usize) -> usize {
-            *num = (*num % 12345) * (*num % 2949) + 7;
-            *num
-        }
-
         const TEXT_LENGTH: usize = 1000;
         let mut test_bytes: Vec<u8> = vec![0; TEXT_LENGTH];
         let mut rand;
@@ -125,17 +167,17 @@ mod tests {
             rand = n;
             // Some primitive random number generator.
             for j in 0..TEXT_LENGTH {
-                if j >= 1 && next_random(&mut rand) & 1 == 0 {
+                if j >= 1 && testing::next_random(&mut rand) & 1 == 0 {
                     test_bytes[j] = test_bytes[j - 1];
                 } else {
-                    test_bytes[j] = ((next_random(&mut rand) % 16) + ('a' as usize)) as u8;
+                    test_bytes[j] = ((testing::next_random(&mut rand) % 16) + ('a' as usize)) as u8;
                 }
             }
 
             if DEBUG_LEVEL >= 1 {
                 println!(
                     "Test string: {}",
-                    String::from_utf8_lossy(&test_bytes).to_string()
+                    String::from_utf8_lossy(&test_bytes)
                 );
             }
 
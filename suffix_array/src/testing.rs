@@ -1,7 +1,14 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 pub mod testing {
     use super::super::suffix_array::TextSize;
 
+    // A primitive random function.
+    pub fn next_random(num: &mut usize) -> usize {
+        *num = (*num % 12345) * (*num % 2949) + 7;
+        *num
+    }
+
     // Naively computes a suffix array.
     pub fn naive_suffix_array(text: &[u8]) -> Vec<TextSize> {
         let mut suffix_array: Vec<TextSize> = Vec::with_capacity(text.len());
@@ -12,6 +19,15 @@ pub mod testing {
         suffix_array
     }
 
+    // Naively computes a circular suffix array, by sorting the rotations.
+    pub fn naive_circular_suffix_array(text: &[u8]) -> Vec<TextSize> {
+        let len = text.len();
+        let mut suffix_array: Vec<TextSize> = (0..len as TextSize).collect();
+        let rotation = |i: TextSize| text[i as usize..].iter().chain(text[..i as usize].iter());
+        suffix_array.sort_by(|&a, &b| rotation(a).cmp(rotation(b)));
+        suffix_array
+    }
+
     // Compare two suffix arrays and output differences if any.
     pub fn compare_suffix_arrays(
         a: &mut dyn Iterator<Item = TextSize>,
@@ -0,0 +1,157 @@
+// Suffix array of a UTF-8 text, over its code points.
+//
+// Only the suffixes that start at char boundaries are indexed, so each position slices a valid
+// &str. The chars are sorted by their scalar values with the integer alphabet path of SA-IS,
+// after mapping them to their ranks among the distinct chars of the text.
+//
+// UTF-8 preserves the order of scalar values, so the suffixes are also in the order of their
+// bytes, and the byte-level searches work on the suffix array as usual.
+//
+// A text that is not valid UTF-8 is indexed at the starts of its valid chars and at each byte of
+// its invalid sequences, by filtering the suffix array of its bytes.
+
+use super::sa_is::SaIsBuilder;
+use super::suffix_array::{SuffixArray, SuffixArrayBuilder, TextSize};
+
+// Builds suffix arrays over the code points of UTF-8 texts.
+pub struct Utf8Builder {}
+
+impl Utf8Builder {
+    pub fn new() -> Utf8Builder {
+        Utf8Builder {}
+    }
+
+    // Returns the byte offsets of the char boundaries of the text, in suffix order.
+    pub fn build_str(&self, text: &str) -> Box<dyn SuffixArray> {
+        let mut offsets: Vec<TextSize> = Vec::with_capacity(text.len());
+        let mut chars: Vec<u32> = Vec::with_capacity(text.len());
+        for (offset, ch) in text.char_indices() {
+            offsets.push(offset as TextSize);
+            chars.push(ch as u32);
+        }
+        if chars.is_empty() {
+            return Box::new(Utf8SuffixArray { sa: Vec::new() });
+        }
+
+        // Map the chars to their ranks, to keep the alphabet small.
+        let mut alphabet: Vec<u32> = chars.clone();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        for ch in chars.iter_mut() {
+            *ch = alphabet.binary_search(ch).unwrap() as u32;
+        }
+
+        let mut sa = SaIsBuilder::new().build_u32(&chars, alphabet.len() as TextSize);
+        for pos in sa.iter_mut() {
+            *pos = offsets[*pos as usize];
+        }
+        Box::new(Utf8SuffixArray { sa })
+    }
+}
+
+impl Default for Utf8Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Utf8SuffixArray {
+    sa: Vec<TextSize>,
+}
+
+impl SuffixArray for Utf8SuffixArray {
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = TextSize> + 'a> {
+        Box::new(self.sa.iter().copied())
+    }
+
+    fn array(&self) -> &[TextSize] {
+        &self.sa
+    }
+}
+
+impl SuffixArrayBuilder for Utf8Builder {
+    fn build(&self, text: &[u8]) -> Box<dyn SuffixArray> {
+        if let Ok(text) = std::str::from_utf8(text) {
+            return self.build_str(text);
+        }
+
+        let mut is_start: Vec<bool> = vec![false; text.len()];
+        let mut offset = 0;
+        for chunk in text.utf8_chunks() {
+            for (pos, _) in chunk.valid().char_indices() {
+                is_start[offset + pos] = true;
+            }
+            offset += chunk.valid().len();
+            let end = offset + chunk.invalid().len();
+            is_start[offset..end].fill(true);
+            offset = end;
+        }
+        let sa: Vec<TextSize> = SaIsBuilder::new()
+            .build(text)
+            .iter()
+            .filter(|&pos| is_start[pos as usize])
+            .collect();
+        Box::new(Utf8SuffixArray { sa })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+
+    fn check(text: &str) {
+        let sa = Utf8Builder::new().build_str(text);
+        let suffixes: Vec<&str> = sa.iter().map(|pos| &text[pos as usize..]).collect();
+        assert_eq!(suffixes.len(), text.chars().count());
+        assert!(suffixes.windows(2).all(|w| w[0] < w[1]));
+
+        // Same as the byte suffix array, without the suffixes inside chars.
+        let bytes = SaIsBuilder::new().build(text.as_bytes());
+        let boundaries: Vec<TextSize> = bytes
+            .iter()
+            .filter(|&pos| text.is_char_boundary(pos as usize))
+            .collect();
+        assert_eq!(sa.array(), boundaries);
+    }
+
+    #[test]
+    fn code_points() {
+        check("banana");
+        check("naïve café — naïveté");
+        check("日本語のテキスト、日本の語");
+        check("🦀🦀a🦀é🦀");
+        assert!(Utf8Builder::new().build_str("").array().is_empty());
+
+        let text = "über über uber";
+        let sa = Utf8Builder::new().build(text.as_bytes());
+        assert_eq!(sa.locate(text.as_bytes(), "über".as_bytes()), [0, 6]);
+        assert_eq!(sa.locate(text.as_bytes(), "ber".as_bytes()), [2, 8, 13]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        // "é", a lone continuation byte, a truncated "日", "a", and an invalid byte.
+        let text = b"\xc3\xa9\x80\xe6\x97a\xff";
+        let sa = Utf8Builder::new().build(text);
+        let mut positions: Vec<TextSize> = sa.iter().collect();
+        positions.sort_unstable();
+        assert_eq!(positions, [0, 2, 3, 4, 5, 6]);
+        let suffixes: Vec<&[u8]> = sa.iter().map(|pos| &text[pos as usize..]).collect();
+        assert!(suffixes.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(sa.locate(text, b"a"), [5]);
+        assert_eq!(sa.locate(text, "é".as_bytes()), [0]);
+    }
+
+    #[test]
+    fn random_code_points() {
+        let mut rand: usize = 3;
+        let chars = ['a', 'é', 'ß', '日', '本', '🦀', '\u{10FFFF}'];
+        for n in 1..30 {
+            let text: String = (0..n * 7)
+                .map(|_| chars[testing::next_random(&mut rand) % (2 + n % 6)])
+                .collect();
+            check(&text);
+        }
+    }
+}
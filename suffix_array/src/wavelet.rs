@@ -0,0 +1,240 @@
+// Wavelet matrix, for rank and select over large alphabets, such as the BWT of token IDs.
+//
+// Level l holds bit l of the values, from the most significant, in a BitVector. The values are
+// then stably partitioned by that bit, zeros first, to give the order of level l + 1. Each query
+// follows a position or a range down the levels, with rank() on each level, so it takes
+// O(log sigma) time for an alphabet of size sigma, and about n log sigma bits of space.
+
+use std::ops::Range;
+
+use super::bitvector::BitVector;
+use super::bwt::Bwt;
+use super::suffix_array::TextSize;
+
+pub struct WaveletMatrix {
+    len: usize,
+    levels: Vec<BitVector>,
+
+    // Number of zeros in each level.
+    zeros: Vec<usize>,
+}
+
+impl WaveletMatrix {
+    pub fn new(values: &[u32]) -> WaveletMatrix {
+        let max = values.iter().copied().max().unwrap_or(0);
+        let depth = (u32::BITS - max.leading_zeros()) as usize;
+        let mut levels: Vec<BitVector> = Vec::with_capacity(depth);
+        let mut zeros: Vec<usize> = Vec::with_capacity(depth);
+        let mut current: Vec<u32> = values.to_vec();
+        for level in 0..depth {
+            let shift = depth - 1 - level;
+            let bits: Vec<bool> = current.iter().map(|&v| v >> shift & 1 != 0).collect();
+            let (mut next, ones): (Vec<u32>, Vec<u32>) =
+                current.iter().partition(|&&v| v >> shift & 1 == 0);
+            zeros.push(next.len());
+            next.extend(ones);
+            levels.push(BitVector::new(&bits));
+            current = next;
+        }
+        WaveletMatrix {
+            len: values.len(),
+            levels,
+            zeros,
+        }
+    }
+
+    // The wavelet matrix of the bytes of a BWT.
+    pub fn from_bwt(bwt: &Bwt) -> WaveletMatrix {
+        let values: Vec<u32> = bwt.bwt().iter().map(|&ch| ch as u32).collect();
+        WaveletMatrix::new(&values)
+    }
+
+    // The wavelet matrix of the BWT of a text of token IDs, from its suffix array, as built by
+    // SaIsBuilder::build_u32. The sentinel is kept in the BWT as 0, so each token ID is shifted
+    // up by 1, and the BWT has one more value than the text.
+    pub fn from_u32_suffix_array(text: &[u32], sa: &[TextSize]) -> WaveletMatrix {
+        assert!(text.len() == sa.len());
+        assert!(text.iter().all(|&id| id < u32::MAX));
+        let Some(&last) = text.last() else {
+            return WaveletMatrix::new(&[]);
+        };
+        let mut bwt: Vec<u32> = Vec::with_capacity(text.len() + 1);
+        bwt.push(last + 1);
+        bwt.extend(sa.iter().map(|&pos| match pos {
+            0 => 0,
+            pos => text[pos as usize - 1] + 1,
+        }));
+        WaveletMatrix::new(&bwt)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn depth(&self) -> usize {
+        self.levels.len()
+    }
+
+    // Maps a position of a level to the next level, following the given bit.
+    fn next_pos(&self, level: usize, pos: usize, bit: bool) -> usize {
+        let bits = &self.levels[level];
+        if bit {
+            self.zeros[level] + bits.rank1(pos)
+        } else {
+            bits.rank0(pos)
+        }
+    }
+
+    // Returns the value at position i.
+    pub fn access(&self, mut i: usize) -> u32 {
+        assert!(i < self.len);
+        let mut value = 0;
+        for level in 0..self.depth() {
+            let bit = self.levels[level].get(i);
+            value = value << 1 | bit as u32;
+            i = self.next_pos(level, i, bit);
+        }
+        value
+    }
+
+    // Returns the range of the value in the last level, restricted to the first end positions.
+    fn value_range(&self, value: u32, end: usize) -> Option<Range<usize>> {
+        if self.depth() < u32::BITS as usize && value >> self.depth() != 0 {
+            return None;
+        }
+        let mut range = 0..end;
+        for level in 0..self.depth() {
+            let bit = value >> (self.depth() - 1 - level) & 1 != 0;
+            range = self.next_pos(level, range.start, bit)..self.next_pos(level, range.end, bit);
+        }
+        Some(range)
+    }
+
+    // Returns the number of occurrences of the value before position i.
+    pub fn rank(&self, value: u32, i: usize) -> usize {
+        assert!(i <= self.len);
+        self.value_range(value, i).map_or(0, |range| range.len())
+    }
+
+    // Returns the position of the occurrence of the value of rank k, counting from 0.
+    pub fn select(&self, value: u32, k: usize) -> Option<usize> {
+        let range = self.value_range(value, self.len)?;
+        if k >= range.len() {
+            return None;
+        }
+        let mut pos = range.start + k;
+        for level in (0..self.depth()).rev() {
+            let bits = &self.levels[level];
+            pos = if value >> (self.depth() - 1 - level) & 1 != 0 {
+                bits.select1(pos - self.zeros[level]).unwrap()
+            } else {
+                bits.select0(pos).unwrap()
+            };
+        }
+        Some(pos)
+    }
+
+    // Returns the k-th smallest value in the range of positions, counting from 0.
+    pub fn quantile(&self, range: Range<usize>, mut k: usize) -> u32 {
+        assert!(range.end <= self.len && k < range.len());
+        let mut range = range;
+        let mut value = 0;
+        for level in 0..self.depth() {
+            let bits = &self.levels[level];
+            let zeros = bits.rank0(range.end) - bits.rank0(range.start);
+            let bit = k >= zeros;
+            if bit {
+                k -= zeros;
+            }
+            value = value << 1 | bit as u32;
+            range = self.next_pos(level, range.start, bit)..self.next_pos(level, range.end, bit);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+
+    #[test]
+    fn matches_naive() {
+        let mut rand: usize = 23;
+        for n in [0, 1, 2, 10, 100, 700] {
+            for max in [0, 1, 5, 1000, u32::MAX] {
+                let values: Vec<u32> = (0..n)
+                    .map(|_| {
+                        let r = testing::next_random(&mut rand);
+                        // Mostly a few values, to have repeats.
+                        [0, max, max / 2, (r as u32) % (max / 3 + 1)][r % 4]
+                    })
+                    .collect();
+                let wm = WaveletMatrix::new(&values);
+                assert_eq!(wm.len(), n);
+                for (i, &value) in values.iter().enumerate() {
+                    assert_eq!(wm.access(i), value);
+                    let rank = values[..i].iter().filter(|&&v| v == value).count();
+                    assert_eq!(wm.rank(value, i), rank);
+                    assert_eq!(wm.select(value, rank), Some(i));
+                }
+                for value in [0, 1, max / 2, max.wrapping_add(1), u32::MAX] {
+                    let count = values.iter().filter(|&&v| v == value).count();
+                    assert_eq!(wm.rank(value, n), count);
+                    assert_eq!(wm.select(value, count), None);
+                }
+                for _ in 0..10 {
+                    let r = testing::next_random(&mut rand);
+                    let start = r % (n + 1);
+                    let end = start + r / 7 % (n - start + 1);
+                    let mut sorted = values[start..end].to_vec();
+                    sorted.sort_unstable();
+                    for (k, &value) in sorted.iter().enumerate() {
+                        assert_eq!(wm.quantile(start..end, k), value);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn token_bwt() {
+        // BWT of a sequence of token IDs, with the sentinel as 0 and the IDs shifted by 1.
+        let tokens: Vec<u32> = vec![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 9, 1000, 5, 9];
+        let sa = SaIsBuilder::new().build_u32(&tokens, 1001);
+        let wm = WaveletMatrix::from_u32_suffix_array(&tokens, &sa);
+        assert_eq!(wm.len(), tokens.len() + 1);
+        assert_eq!(wm.access(0), 10);
+        assert_eq!(wm.rank(0, wm.len()), 1);
+
+        // Backward search: count the occurrences of "5 9". counts[c] is the number of shifted
+        // IDs less than c, not counting the sentinel.
+        let mut counts: Vec<usize> = vec![0; 1003];
+        for &id in &tokens {
+            counts[id as usize + 2] += 1;
+        }
+        for c in 1..counts.len() {
+            counts[c] += counts[c - 1];
+        }
+        let mut range = 0..wm.len();
+        for &id in [5, 9].iter().rev() {
+            let c = id + 1;
+            let start = counts[c as usize] + 1;
+            range = start + wm.rank(c, range.start)..start + wm.rank(c, range.end);
+        }
+        assert_eq!(range.len(), 3);
+
+        assert!(WaveletMatrix::from_u32_suffix_array(&[], &[]).is_empty());
+
+        let bytes = Bwt::new(&SaIsBuilder::new(), b"banana");
+        let wm = WaveletMatrix::from_bwt(&bytes);
+        assert_eq!(wm.rank(b'a' as u32, 6), 3);
+        assert_eq!(wm.select(b'n' as u32, 1), Some(2));
+        assert_eq!(wm.quantile(0..6, 5), b'n' as u32);
+    }
+}
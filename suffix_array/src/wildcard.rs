@@ -0,0 +1,313 @@
+// Pattern search with single char wildcards and byte classes over a suffix array.
+//
+// Pattern syntax:
+//   ?        matches any byte.
+//   [abc]    matches any of the bytes a, b or c.
+//   [a-z]    matches any byte in the range a to z. Ranges and bytes can be mixed: [0-9a-f_].
+//   [^...]   matches any byte not in the class.
+//   \c       matches the byte c literally, e.g. \? or \[.
+//   Other bytes match themselves.
+// e.g. "ERR?R" matches "ERROR" and "ERRXR", and "[0-9][0-9]:" matches "12:".
+
+use std::fmt;
+use std::ops::Range;
+
+use super::search::{child_ranges, narrow_range};
+use super::suffix_array::TextSize;
+
+// A set of bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ByteClass {
+    bits: [u64; 4],
+}
+
+impl ByteClass {
+    pub fn empty() -> ByteClass {
+        ByteClass { bits: [0; 4] }
+    }
+
+    pub fn any() -> ByteClass {
+        ByteClass { bits: [!0; 4] }
+    }
+
+    pub fn byte(ch: u8) -> ByteClass {
+        let mut class = ByteClass::empty();
+        class.insert(ch);
+        class
+    }
+
+    pub fn insert(&mut self, ch: u8) {
+        self.bits[ch as usize / 64] |= 1 << (ch % 64);
+    }
+
+    pub fn contains(&self, ch: u8) -> bool {
+        self.bits[ch as usize / 64] & (1 << (ch % 64)) != 0
+    }
+
+    pub fn negate(&self) -> ByteClass {
+        ByteClass {
+            bits: self.bits.map(|b| !b),
+        }
+    }
+
+    // Returns the byte if this class contains exactly one byte.
+    pub fn single(&self) -> Option<u8> {
+        let count: u32 = self.bits.iter().map(|b| b.count_ones()).sum();
+        if count == 1 {
+            (0..=255).find(|&ch| self.contains(ch))
+        } else {
+            None
+        }
+    }
+}
+
+// An error in a pattern, at a byte offset of the pattern.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PatternError {
+    pub pos: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.pos)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+// Reads a byte of a byte class at pos, which may be escaped, and returns it with the offset after
+// it.
+fn class_byte(pattern: &[u8], pos: usize) -> Result<(u8, usize), PatternError> {
+    match pattern[pos] {
+        b'\\' => match pattern.get(pos + 1) {
+            Some(&ch) => Ok((ch, pos + 2)),
+            None => Err(PatternError {
+                pos,
+                message: "trailing backslash",
+            }),
+        },
+        ch => Ok((ch, pos + 1)),
+    }
+}
+
+// Parses a byte class after its opening '[', and returns it with the offset after its ']'.
+pub fn parse_byte_class(
+    pattern: &[u8],
+    mut pos: usize,
+) -> Result<(ByteClass, usize), PatternError> {
+    let start = pos;
+    let negated = pattern.get(pos) == Some(&b'^');
+    if negated {
+        pos += 1;
+    }
+
+    let mut class = ByteClass::empty();
+    let mut first = true;
+    loop {
+        match pattern.get(pos) {
+            None => {
+                return Err(PatternError {
+                    pos: start - 1,
+                    message: "unterminated byte class",
+                })
+            }
+            // A ']' right after '[' or '[^' is a literal.
+            Some(b']') if !first => break,
+            Some(_) => {}
+        }
+        let (ch, next) = class_byte(pattern, pos)?;
+        pos = next;
+        first = false;
+
+        // A range, unless the '-' is the last char of the class.
+        if pattern.get(pos) == Some(&b'-') && pattern.get(pos + 1).is_some_and(|&c| c != b']') {
+            let (end, next) = class_byte(pattern, pos + 1)?;
+            if end < ch {
+                return Err(PatternError {
+                    pos,
+                    message: "invalid byte range",
+                });
+            }
+            for c in ch..=end {
+                class.insert(c);
+            }
+            pos = next;
+        } else {
+            class.insert(ch);
+        }
+    }
+    let class = if negated { class.negate() } else { class };
+    Ok((class, pos + 1))
+}
+
+// A parsed pattern: a sequence of byte classes, one for each matched byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WildcardPattern {
+    classes: Vec<ByteClass>,
+}
+
+impl WildcardPattern {
+    pub fn parse(pattern: &[u8]) -> Result<WildcardPattern, PatternError> {
+        let mut classes: Vec<ByteClass> = Vec::new();
+        let mut pos = 0;
+        while pos < pattern.len() {
+            let class = match pattern[pos] {
+                b'?' => ByteClass::any(),
+                b'[' => {
+                    let (class, next) = parse_byte_class(pattern, pos + 1)?;
+                    classes.push(class);
+                    pos = next;
+                    continue;
+                }
+                b'\\' => {
+                    pos += 1;
+                    ByteClass::byte(*pattern.get(pos).ok_or(PatternError {
+                        pos: pos - 1,
+                        message: "trailing backslash",
+                    })?)
+                }
+                ch => ByteClass::byte(ch),
+            };
+            classes.push(class);
+            pos += 1;
+        }
+        Ok(WildcardPattern { classes })
+    }
+
+    // Number of bytes matched by the pattern.
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    // Whether the pattern matches text[pos..pos + len].
+    pub fn matches_at(&self, text: &[u8], pos: usize) -> bool {
+        pos + self.len() <= text.len()
+            && self
+                .classes
+                .iter()
+                .zip(text[pos..].iter())
+                .all(|(class, &ch)| class.contains(ch))
+    }
+}
+
+// Finds all positions where the pattern matches, in text order.
+pub fn wildcard_search(text: &[u8], sa: &[TextSize], pattern: &WildcardPattern) -> Vec<TextSize> {
+    let mut positions: Vec<TextSize> = Vec::new();
+    for range in wildcard_ranges(text, sa, pattern) {
+        positions.extend_from_slice(&sa[range]);
+    }
+    positions.sort_unstable();
+    positions
+}
+
+// Returns the ranges of rows whose suffixes start with a match of the pattern, in row order.
+pub fn wildcard_ranges(
+    text: &[u8],
+    sa: &[TextSize],
+    pattern: &WildcardPattern,
+) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    search_range(text, sa, &pattern.classes, 0..sa.len(), 0, &mut ranges);
+    ranges
+}
+
+// Searches the suffixes in range, which match classes[..depth].
+fn search_range(
+    text: &[u8],
+    sa: &[TextSize],
+    classes: &[ByteClass],
+    mut range: Range<usize>,
+    mut depth: usize,
+    ranges: &mut Vec<Range<usize>>,
+) {
+    // Single bytes narrow the range without branching.
+    while let Some(ch) = classes.get(depth).and_then(|class| class.single()) {
+        if range.is_empty() {
+            return;
+        }
+        range = narrow_range(text, sa, range, depth, ch);
+        depth += 1;
+    }
+    if range.is_empty() {
+        return;
+    }
+    if depth == classes.len() {
+        ranges.push(range);
+        return;
+    }
+
+    for (ch, child) in child_ranges(text, sa, range, depth) {
+        if classes[depth].contains(ch) {
+            search_range(text, sa, classes, child, depth + 1, ranges);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sa_is::SaIsBuilder;
+    use crate::suffix_array::SuffixArrayBuilder;
+
+    fn naive_search(text: &[u8], pattern: &WildcardPattern) -> Vec<TextSize> {
+        (0..text.len())
+            .filter(|&pos| pattern.matches_at(text, pos))
+            .map(|pos| pos as TextSize)
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let pattern = WildcardPattern::parse(b"a?[0-9x][^a-z]\\?[]-]").unwrap();
+        assert_eq!(pattern.len(), 6);
+        assert!(pattern.matches_at(b"ab5Z?]", 0));
+        assert!(pattern.matches_at(b"a xA?-", 0));
+        assert!(!pattern.matches_at(b"abyA?-", 0));
+        assert!(!pattern.matches_at(b"ab5z?-", 0));
+
+        assert_eq!(
+            WildcardPattern::parse(b"ab[cd"),
+            Err(PatternError {
+                pos: 2,
+                message: "unterminated byte class"
+            })
+        );
+        assert!(WildcardPattern::parse(b"[z-a]").is_err());
+        assert!(WildcardPattern::parse(b"ab\\").is_err());
+        assert!(WildcardPattern::parse(b"[a-\\").is_err());
+
+        // Escaped range ends.
+        let pattern = WildcardPattern::parse(b"[Z-\\]][\\]-\\^]").unwrap();
+        assert!(pattern.matches_at(b"\\^", 0));
+        assert!(pattern.matches_at(b"]]", 0));
+        assert!(!pattern.matches_at(b"Y]", 0));
+        assert!(!pattern.matches_at(b"Z-", 0));
+    }
+
+    #[test]
+    fn matches_naive() {
+        let text = b"12:30 ERROR disk full\n12:31 ERRXR net\n09:00 error ok ERR\n";
+        let sa = SaIsBuilder::new().build(text);
+        let patterns: [&[u8]; 7] = [
+            b"ERR?R",
+            b"[0-9][0-9]:",
+            b"[^0-9 ]",
+            b"?",
+            b"[Ee][Rr][Rr]",
+            b"??:?0",
+            b"missing?",
+        ];
+        for pattern in patterns {
+            let pattern = WildcardPattern::parse(pattern).unwrap();
+            assert_eq!(
+                wildcard_search(text, sa.array(), &pattern),
+                naive_search(text, &pattern)
+            );
+        }
+    }
+}
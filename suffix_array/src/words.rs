@@ -0,0 +1,220 @@
+// Word-level suffix array, for phrase search.
+//
+// The text is split into tokens, and each distinct token gets an ID in the lexicographic order of
+// the tokens. The suffix array is built over the sequence of token IDs with the integer alphabet
+// path of SA-IS, so suffixes start only at token boundaries.
+// A phrase is tokenized in the same way, and matches consecutive tokens of the text regardless
+// of what separates them. e.g. the phrase "be or" matches "be, or".
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::sa_is::SaIsBuilder;
+use super::search::narrow_range;
+use super::suffix_array::TextSize;
+
+// Splits a text into tokens.
+pub trait Tokenizer {
+    // Returns the byte ranges of the tokens, in text order.
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>>;
+}
+
+// Tokens are the maximal runs of bytes that are not ASCII whitespace or punctuation. Non-ASCII
+// bytes are part of tokens, so UTF-8 words are kept whole.
+pub struct DefaultTokenizer {}
+
+impl DefaultTokenizer {
+    pub fn new() -> DefaultTokenizer {
+        DefaultTokenizer {}
+    }
+}
+
+impl Default for DefaultTokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tokenizer for DefaultTokenizer {
+    fn tokenize(&self, text: &[u8]) -> Vec<Range<usize>> {
+        let is_separator = |ch: &u8| ch.is_ascii_whitespace() || ch.is_ascii_punctuation();
+        let mut tokens: Vec<Range<usize>> = Vec::new();
+        let mut pos = 0;
+        while pos < text.len() {
+            match text[pos..].iter().position(|ch| !is_separator(ch)) {
+                None => break,
+                Some(skip) => pos += skip,
+            }
+            let len = text[pos..]
+                .iter()
+                .position(is_separator)
+                .unwrap_or(text.len() - pos);
+            tokens.push(pos..pos + len);
+            pos += len;
+        }
+        tokens
+    }
+}
+
+pub struct WordSuffixArray<'a> {
+    text: &'a [u8],
+    tokenizer: Box<dyn Tokenizer>,
+
+    // Byte ranges of the tokens of the text.
+    tokens: Vec<Range<usize>>,
+
+    // ID of each token of the text, and the IDs of the distinct tokens.
+    token_ids: Vec<u32>,
+    ids: HashMap<&'a [u8], u32>,
+
+    // Token indices, in suffix order.
+    sa: Vec<TextSize>,
+}
+
+impl<'a> WordSuffixArray<'a> {
+    pub fn new(text: &'a [u8], tokenizer: Box<dyn Tokenizer>) -> WordSuffixArray<'a> {
+        let tokens = tokenizer.tokenize(text);
+        let mut vocabulary: Vec<&[u8]> = tokens.iter().map(|t| &text[t.clone()]).collect();
+        vocabulary.sort_unstable();
+        vocabulary.dedup();
+        let ids: HashMap<&[u8], u32> = vocabulary
+            .iter()
+            .enumerate()
+            .map(|(id, &token)| (token, id as u32))
+            .collect();
+
+        let token_ids: Vec<u32> = tokens.iter().map(|t| ids[&text[t.clone()]]).collect();
+        let sa = if token_ids.is_empty() {
+            Vec::new()
+        } else {
+            SaIsBuilder::new().build_u32(&token_ids, vocabulary.len() as TextSize)
+        };
+        WordSuffixArray {
+            text,
+            tokenizer,
+            tokens,
+            token_ids,
+            ids,
+            sa,
+        }
+    }
+
+    // Token indices, in suffix order.
+    pub fn array(&self) -> &[TextSize] {
+        &self.sa
+    }
+
+    // Number of distinct tokens.
+    pub fn vocabulary_size(&self) -> usize {
+        self.ids.len()
+    }
+
+    // Returns the byte range of a token of the text.
+    pub fn token(&self, index: usize) -> Range<usize> {
+        self.tokens[index].clone()
+    }
+
+    // Returns the range of rows whose suffixes start with the token IDs.
+    fn find_range(&self, phrase: &[u32]) -> Range<usize> {
+        let mut range = 0..self.sa.len();
+        for (depth, &id) in phrase.iter().enumerate() {
+            range = narrow_range(&self.token_ids, &self.sa, range, depth, id);
+        }
+        range
+    }
+
+    // Returns the byte ranges of the occurrences of the phrase, from the start of its first token
+    // to the end of its last token, in text order. An empty phrase has no occurrences.
+    pub fn find_phrase(&self, phrase: &[u8]) -> Vec<Range<usize>> {
+        let ids: Option<Vec<u32>> = self
+            .tokenizer
+            .tokenize(phrase)
+            .into_iter()
+            .map(|t| self.ids.get(&phrase[t]).copied())
+            .collect();
+        let ids = match ids {
+            Some(ids) if !ids.is_empty() => ids,
+            _ => return Vec::new(),
+        };
+
+        let mut starts: Vec<usize> = self.sa[self.find_range(&ids)]
+            .iter()
+            .map(|&index| index as usize)
+            .collect();
+        starts.sort_unstable();
+        starts
+            .into_iter()
+            .map(|index| self.tokens[index].start..self.tokens[index + ids.len() - 1].end)
+            .collect()
+    }
+
+    // Returns the text of an occurrence returned by find_phrase().
+    pub fn text(&self, range: Range<usize>) -> &'a [u8] {
+        &self.text[range]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::testing;
+    use super::*;
+
+    #[test]
+    fn tokenize() {
+        let tokens = DefaultTokenizer::new().tokenize(b"  To be, or not-to be? \xc3\xa9t\xc3\xa9");
+        assert_eq!(tokens, [2..4, 5..7, 9..11, 12..15, 16..18, 19..21, 23..28]);
+        assert!(DefaultTokenizer::new().tokenize(b" ,. ").is_empty());
+    }
+
+    #[test]
+    fn phrases() {
+        let text = b"To be, or not to be: that is the question. To be fair, not to be.";
+        let index = WordSuffixArray::new(text, Box::new(DefaultTokenizer::new()));
+        assert_eq!(index.vocabulary_size(), 10);
+        assert_eq!(index.array().len(), 16);
+
+        let found = |phrase: &[u8]| -> Vec<&[u8]> {
+            index
+                .find_phrase(phrase)
+                .into_iter()
+                .map(|r| index.text(r))
+                .collect()
+        };
+        assert_eq!(found(b"to be"), [&b"to be"[..], b"to be"]);
+        assert_eq!(found(b"be or"), [&b"be, or"[..]]);
+        assert_eq!(found(b"not, to be"), [&b"not to be"[..], b"not to be"]);
+        assert_eq!(found(b"be"), [&b"be"[..], b"be", b"be", b"be"]);
+        // "be" must be a whole token.
+        assert!(found(b"b").is_empty());
+        assert!(found(b"to be or").is_empty());
+        assert!(found(b"").is_empty());
+        assert!(found(b"missing").is_empty());
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rand: usize = 13;
+        let words: [&[u8]; 5] = [b"a", b"b", b"ab", b"ba", b"c"];
+        let mut next = || testing::next_random(&mut rand);
+        let mut text: Vec<u8> = Vec::new();
+        for _ in 0..300 {
+            text.extend_from_slice(words[next() % words.len()]);
+            text.extend_from_slice([&b" "[..], b", ", b".\n"][next() % 3]);
+        }
+        let index = WordSuffixArray::new(&text, Box::new(DefaultTokenizer::new()));
+        let tokens: Vec<&[u8]> = index.tokens.iter().map(|t| &text[t.clone()]).collect();
+        for len in 1..5 {
+            let phrase: Vec<&[u8]> = (0..len).map(|_| words[next() % words.len()]).collect();
+            let expected: Vec<usize> = (0..tokens.len())
+                .filter(|&i| tokens[i..].starts_with(&phrase))
+                .map(|i| index.token(i).start)
+                .collect();
+            let found: Vec<usize> = index
+                .find_phrase(&phrase.join(&b' '))
+                .iter()
+                .map(|r| r.start)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+}